@@ -0,0 +1,66 @@
+use std::future::Future;
+
+use serde_json::Value;
+
+use crate::{ExifTool, ExifToolError, ExifToolOutput};
+
+/// The core calls of [`ExifTool`], for code which should also run against a stand-in, such
+/// as `testing::MockExifTool` with the `testing` feature, rather than a real exiftool.
+pub trait ExifToolApi {
+    /// See [`ExifTool::execute`].
+    fn execute(
+        &self,
+        params: Vec<String>,
+    ) -> impl Future<Output = Result<ExifToolOutput, ExifToolError>> + Send;
+
+    /// See [`ExifTool::execute_json`].
+    fn execute_json(
+        &self,
+        params: Vec<String>,
+    ) -> impl Future<Output = Result<Value, ExifToolError>> + Send;
+
+    /// See [`ExifTool::get_tags`]. By default, this passes the same arguments as
+    /// [`ExifTool::get_tags`] on to [`ExifToolApi::execute_json`].
+    fn get_tags(
+        &self,
+        mut params: Vec<String>,
+        tags: Vec<String>,
+        files: Vec<String>,
+    ) -> impl Future<Output = Result<Value, ExifToolError>> + Send {
+        params.extend(tags.into_iter().map(|tag| format!("-{tag}")));
+        params.extend(files);
+        self.execute_json(params)
+    }
+
+    /// See [`ExifTool::preview`].
+    fn preview(&self, path: &str) -> impl Future<Output = Result<Vec<u8>, ExifToolError>> + Send;
+}
+
+impl ExifToolApi for ExifTool {
+    fn execute(
+        &self,
+        params: Vec<String>,
+    ) -> impl Future<Output = Result<ExifToolOutput, ExifToolError>> + Send {
+        ExifTool::execute(self, params)
+    }
+
+    fn execute_json(
+        &self,
+        params: Vec<String>,
+    ) -> impl Future<Output = Result<Value, ExifToolError>> + Send {
+        ExifTool::execute_json(self, params)
+    }
+
+    fn get_tags(
+        &self,
+        params: Vec<String>,
+        tags: Vec<String>,
+        files: Vec<String>,
+    ) -> impl Future<Output = Result<Value, ExifToolError>> + Send {
+        ExifTool::get_tags(self, params, tags, files)
+    }
+
+    fn preview(&self, path: &str) -> impl Future<Output = Result<Vec<u8>, ExifToolError>> + Send {
+        ExifTool::preview(self, path)
+    }
+}
@@ -0,0 +1,69 @@
+//! A blocking interface to exiftool, for use outside of an async runtime.
+//!
+//! Each [`ExifTool`] here runs the async [`crate::ExifTool`] on its own single-threaded tokio
+//! runtime, so callers don't need to set one up. Calling these methods from within an
+//! async runtime is unsupported, and will panic.
+
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+use crate::{ExifToolBuilder, ExifToolError, ExifToolOutput};
+
+pub struct ExifTool {
+    // Declared first so that the process is shut down before the runtime it belongs to.
+    inner: crate::ExifTool,
+    runtime: Runtime,
+}
+
+impl ExifTool {
+    /// Spawns exiftool, as found in the `EXIFTOOL` environment variable or on `PATH`.
+    ///
+    /// # Panics
+    /// Panics if exiftool could not be spawned. Use [`ExifTool::try_new`] to handle this case.
+    pub fn new() -> Self {
+        Self::try_new().unwrap()
+    }
+
+    pub fn try_new() -> Result<Self, ExifToolError> {
+        Self::from_builder(ExifToolBuilder::new())
+    }
+
+    pub fn from_builder(builder: ExifToolBuilder) -> Result<Self, ExifToolError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let inner = {
+            let _guard = runtime.enter();
+            builder.build()?
+        };
+        Ok(ExifTool { inner, runtime })
+    }
+
+    pub fn execute(&self, params: Vec<String>) -> Result<ExifToolOutput, ExifToolError> {
+        self.runtime.block_on(self.inner.execute(params))
+    }
+
+    pub fn execute_json(&self, params: Vec<String>) -> Result<Value, ExifToolError> {
+        self.runtime.block_on(self.inner.execute_json(params))
+    }
+
+    pub fn get_tags(
+        &self,
+        params: Vec<String>,
+        tags: Vec<String>,
+        files: Vec<String>,
+    ) -> Result<Value, ExifToolError> {
+        self.runtime
+            .block_on(self.inner.get_tags(params, tags, files))
+    }
+
+    pub fn preview(&self, path: &str) -> Result<Vec<u8>, ExifToolError> {
+        self.runtime.block_on(self.inner.preview(path))
+    }
+}
+
+impl Default for ExifTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,163 @@
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Timelike};
+use serde_json::Value;
+
+use crate::{find_tag, BackupMode, ExifTool, ExifToolError, WriteSummary};
+
+/// When a photo was taken. Files which don't record their UTC offset only have a local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureDateTime {
+    Offset(DateTime<FixedOffset>),
+    /// The camera's local time, in an unknown timezone.
+    Naive(NaiveDateTime),
+}
+
+impl ExifTool {
+    /// Reads when `file` was taken from `DateTimeOriginal`, including sub-second precision
+    /// from `SubSecTimeOriginal` and the UTC offset from `OffsetTimeOriginal` where present.
+    ///
+    /// Returns `None` if the file has no capture time or it is unset (`0000:00:00 00:00:00`).
+    pub async fn capture_datetime(
+        &self,
+        file: &str,
+    ) -> Result<Option<CaptureDateTime>, ExifToolError> {
+        let tags = self
+            .get_tags_for_file(
+                file,
+                &[
+                    "DateTimeOriginal",
+                    "SubSecTimeOriginal",
+                    "OffsetTimeOriginal",
+                ],
+            )
+            .await?;
+        let datetime = match find_tag(&tags, "DateTimeOriginal").and_then(Value::as_str) {
+            Some(datetime) => datetime.trim(),
+            None => return Ok(None),
+        };
+        if datetime.bytes().all(|b| b == b'0' || !b.is_ascii_digit()) {
+            return Ok(None);
+        }
+
+        let invalid = || ExifToolError::InvalidValue {
+            tag: "DateTimeOriginal".to_string(),
+            value: datetime.to_string(),
+        };
+        let (base, mut rest) = datetime
+            .get(..19)
+            .zip(datetime.get(19..))
+            .ok_or_else(invalid)?;
+        let mut naive =
+            NaiveDateTime::parse_from_str(base, "%Y:%m:%d %H:%M:%S").map_err(|_| invalid())?;
+
+        // Some writers append the sub-seconds and offset to DateTimeOriginal itself.
+        let mut subsec = find_tag(&tags, "SubSecTimeOriginal")
+            .map(|v| v.to_string().trim_matches('"').to_string());
+        if let Some(digits) = rest.strip_prefix('.') {
+            let end = digits
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(digits.len());
+            subsec = Some(digits[..end].to_string());
+            rest = &digits[end..];
+        }
+        if let Some(nanos) = subsec.as_deref().and_then(parse_subsec) {
+            naive = naive.with_nanosecond(nanos).ok_or_else(invalid)?;
+        }
+
+        let offset = match rest.trim() {
+            "" => find_tag(&tags, "OffsetTimeOriginal").and_then(Value::as_str),
+            offset => Some(offset),
+        };
+        Ok(Some(match offset.and_then(parse_offset) {
+            Some(offset) => CaptureDateTime::Offset(
+                naive
+                    .and_local_timezone(offset)
+                    .single()
+                    .ok_or_else(invalid)?,
+            ),
+            None => CaptureDateTime::Naive(naive),
+        }))
+    }
+
+    /// Sets the capture, digitization and modification times of `file` (exiftool's
+    /// `AllDates`) to `datetime`, along with the matching `OffsetTime` tags if it has a UTC
+    /// offset.
+    pub async fn set_capture_datetime(
+        &self,
+        file: &str,
+        datetime: CaptureDateTime,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        const FORMAT: &str = "%Y:%m:%d %H:%M:%S";
+        let params = match datetime {
+            CaptureDateTime::Offset(datetime) => {
+                let offset = datetime.format("%:z");
+                vec![
+                    format!("-AllDates={}", datetime.format(FORMAT)),
+                    format!("-OffsetTime={offset}"),
+                    format!("-OffsetTimeOriginal={offset}"),
+                    format!("-OffsetTimeDigitized={offset}"),
+                ]
+            }
+            CaptureDateTime::Naive(datetime) => {
+                vec![format!("-AllDates={}", datetime.format(FORMAT))]
+            }
+        };
+        self.write_all_dates(file, params, backup).await
+    }
+}
+
+/// Converts the fractional digits of a second, such as `"05"`, into nanoseconds.
+fn parse_subsec(digits: &str) -> Option<u32> {
+    let digits = digits.trim();
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits = &digits[..digits.len().min(9)];
+    Some(digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32))
+}
+
+/// Parses an exiftool UTC offset, such as `+02:00` or `Z`.
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    let offset = offset.trim();
+    if offset == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    let sign = match offset.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = offset[1..].split_once(':')?;
+    let seconds = hours.parse::<i32>().ok()? * 3600 + minutes.parse::<i32>().ok()? * 60;
+    FixedOffset::east_opt(sign * seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subsec_digits() {
+        assert_eq!(parse_subsec("5"), Some(500_000_000));
+        assert_eq!(parse_subsec("05"), Some(50_000_000));
+        assert_eq!(parse_subsec(" 123 "), Some(123_000_000));
+        // Digits past nanoseconds are cut off rather than rounded.
+        assert_eq!(parse_subsec("1234567891"), Some(123_456_789));
+        assert_eq!(parse_subsec(""), None);
+        assert_eq!(parse_subsec("-5"), None);
+        assert_eq!(parse_subsec("1.5"), None);
+    }
+
+    #[test]
+    fn utc_offsets() {
+        let hours = |hours: f64| FixedOffset::east_opt((hours * 3600.0) as i32);
+        assert_eq!(parse_offset("Z"), hours(0.0));
+        assert_eq!(parse_offset("+02:00"), hours(2.0));
+        assert_eq!(parse_offset("-05:30"), hours(-5.5));
+        assert_eq!(parse_offset(" +05:45 "), hours(5.75));
+        assert_eq!(parse_offset("02:00"), None);
+        assert_eq!(parse_offset("+0200"), None);
+        assert_eq!(parse_offset("+25:00"), None);
+        assert_eq!(parse_offset(""), None);
+    }
+}
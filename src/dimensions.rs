@@ -0,0 +1,135 @@
+use serde_json::{Map, Value};
+
+use crate::{find_tag, value_as_f64, ExifTool, ExifToolError};
+
+impl ExifTool {
+    /// Reads the size `file` is shown at, as `(width, height)` in pixels, taking its rotation
+    /// into account: a photo stored in landscape but taken in portrait gives a height greater
+    /// than its width. Returns `None` if its size is not recorded.
+    ///
+    /// HEIF, HEIC and AVIF images, along with videos, record their rotation in the container
+    /// as `Rotation`, which viewers apply and which takes precedence here. Other formats only
+    /// have the EXIF `Orientation`.
+    pub async fn display_dimensions(
+        &self,
+        file: &str,
+    ) -> Result<Option<(u32, u32)>, ExifToolError> {
+        let tags = self
+            .get_tags_for_file(
+                file,
+                &[
+                    "Composite:ImageSize#",
+                    "ImageWidth#",
+                    "ImageHeight#",
+                    "Rotation#",
+                    "Orientation#",
+                ],
+            )
+            .await?;
+        let (width, height) = match stored_dimensions(&tags) {
+            Some(dimensions) => dimensions,
+            None => return Ok(None),
+        };
+        Ok(Some(if is_sideways(&tags) {
+            (height, width)
+        } else {
+            (width, height)
+        }))
+    }
+}
+
+/// The dimensions the image is stored at. The composite `ImageSize` is preferred, as it is
+/// taken from the primary image of a HEIF container rather than from whichever of its
+/// items exiftool happened to read `ImageWidth` from.
+pub(crate) fn stored_dimensions(tags: &Map<String, Value>) -> Option<(u32, u32)> {
+    let pixels = |n: f64| (n > 0.0 && n <= u32::MAX as f64).then(|| n.round() as u32);
+    // `-n` prints it as `4032 3024`, though older versions use `4032x3024`.
+    let image_size = find_tag(tags, "ImageSize")
+        .and_then(Value::as_str)
+        .and_then(|size| size.split_once([' ', 'x']))
+        .and_then(|(width, height)| {
+            let width = pixels(width.trim().parse().ok()?)?;
+            let height = pixels(height.trim().parse().ok()?)?;
+            Some((width, height))
+        });
+    image_size.or_else(|| {
+        let width = find_tag(tags, "ImageWidth").and_then(value_as_f64)?;
+        let height = find_tag(tags, "ImageHeight").and_then(value_as_f64)?;
+        Some((pixels(width)?, pixels(height)?))
+    })
+}
+
+/// Whether the image is turned a quarter turn either way when shown.
+fn is_sideways(tags: &Map<String, Value>) -> bool {
+    if let Some(degrees) = find_tag(tags, "Rotation").and_then(value_as_f64) {
+        return (degrees.round() as i64).rem_euclid(180) == 90;
+    }
+    // Orientations 5 to 8 are the ones which transpose the image, with or without a mirror.
+    find_tag(tags, "Orientation")
+        .and_then(value_as_f64)
+        .is_some_and(|orientation| (5.0..=8.0).contains(&orientation))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn tags(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(tags) => tags,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn stored_dimensions_prefers_image_size() {
+        let heic = tags(json!({
+            "ImageSize": "4032 3024",
+            "ImageWidth": 512,
+            "ImageHeight": 512,
+        }));
+        assert_eq!(stored_dimensions(&heic), Some((4032, 3024)));
+        let older = tags(json!({"Composite:ImageSize": "4032x3024"}));
+        assert_eq!(stored_dimensions(&older), Some((4032, 3024)));
+        let plain = tags(json!({"ImageWidth": 640, "ImageHeight": "480"}));
+        assert_eq!(stored_dimensions(&plain), Some((640, 480)));
+    }
+
+    #[test]
+    fn stored_dimensions_missing_or_invalid() {
+        assert_eq!(stored_dimensions(&tags(json!({"ImageWidth": 640}))), None);
+        let zero = tags(json!({"ImageWidth": 0, "ImageHeight": 480}));
+        assert_eq!(stored_dimensions(&zero), None);
+        let garbled = tags(json!({"ImageSize": "wide", "ImageWidth": 2, "ImageHeight": 1}));
+        assert_eq!(stored_dimensions(&garbled), Some((2, 1)));
+    }
+
+    #[test]
+    fn sideways_orientations() {
+        for orientation in 1..=8 {
+            let sideways = is_sideways(&tags(json!({"Orientation": orientation})));
+            assert_eq!(sideways, orientation >= 5, "{orientation}");
+        }
+        assert!(!is_sideways(&tags(json!({}))));
+    }
+
+    #[test]
+    fn rotation_takes_precedence() {
+        for (rotation, sideways) in [
+            (0, false),
+            (90, true),
+            (180, false),
+            (270, true),
+            (-90, true),
+        ] {
+            let heic = tags(json!({"Rotation": rotation, "Orientation": 1}));
+            assert_eq!(is_sideways(&heic), sideways, "{rotation}");
+        }
+        let video = tags(json!({"QuickTime:Rotation": 90}));
+        assert!(is_sideways(&video));
+        let unrotated = tags(json!({"Rotation": 0, "Orientation": 6}));
+        assert!(!is_sideways(&unrotated));
+    }
+}
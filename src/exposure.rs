@@ -0,0 +1,44 @@
+use crate::{find_tag, value_as_f64, ExifTool, ExifToolError};
+
+/// The settings a photo was taken with. Each is `None` if the file doesn't record it.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ExposureSettings {
+    pub iso: Option<u32>,
+    /// The f-number, such as `2.8`.
+    pub aperture: Option<f64>,
+    /// The exposure time in seconds, such as `0.005` for 1/200.
+    pub shutter_speed: Option<f64>,
+    /// The focal length in millimetres.
+    pub focal_length: Option<f64>,
+    /// The exposure compensation in stops.
+    pub exposure_compensation: Option<f64>,
+}
+
+impl ExifTool {
+    /// Reads the ISO, aperture, shutter speed, focal length and exposure compensation of
+    /// `file` as numbers.
+    pub async fn exposure_settings(&self, file: &str) -> Result<ExposureSettings, ExifToolError> {
+        let tags = self
+            .get_tags_for_file(
+                file,
+                &[
+                    "ISO#",
+                    "FNumber#",
+                    "ExposureTime#",
+                    "FocalLength#",
+                    "ExposureCompensation#",
+                ],
+            )
+            .await?;
+        let number = |tag: &str| find_tag(&tags, tag).and_then(value_as_f64);
+        Ok(ExposureSettings {
+            iso: number("ISO")
+                .filter(|iso| *iso >= 0.0 && *iso <= u32::MAX as f64)
+                .map(|iso| iso.round() as u32),
+            aperture: number("FNumber"),
+            shutter_speed: number("ExposureTime"),
+            focal_length: number("FocalLength"),
+            exposure_compensation: number("ExposureCompensation"),
+        })
+    }
+}
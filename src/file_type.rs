@@ -0,0 +1,168 @@
+use serde_json::{Map, Value};
+
+use crate::{find_tag, ExifTool, ExifToolError};
+
+/// The format of a file, as detected by exiftool from its contents rather than its extension.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FileType {
+    Jpeg,
+    Png,
+    Gif,
+    Bmp,
+    Tiff,
+    WebP,
+    Heic,
+    Avif,
+    Psd,
+    Pdf,
+    /// Canon RAW.
+    Cr2,
+    /// Canon RAW, from 2018 on.
+    Cr3,
+    /// Nikon RAW.
+    Nef,
+    /// Sony RAW.
+    Arw,
+    /// Adobe Digital Negative.
+    Dng,
+    /// Fujifilm RAW.
+    Raf,
+    /// Olympus RAW.
+    Orf,
+    /// Panasonic RAW.
+    Rw2,
+    Mp4,
+    Mov,
+    Xmp,
+    /// Any other type, by the name exiftool gives it, such as `MKV`.
+    Other(String),
+}
+
+impl FileType {
+    /// The name exiftool gives this type in its `FileType` tag.
+    pub fn as_str(&self) -> &str {
+        match self {
+            FileType::Jpeg => "JPEG",
+            FileType::Png => "PNG",
+            FileType::Gif => "GIF",
+            FileType::Bmp => "BMP",
+            FileType::Tiff => "TIFF",
+            FileType::WebP => "WEBP",
+            FileType::Heic => "HEIC",
+            FileType::Avif => "AVIF",
+            FileType::Psd => "PSD",
+            FileType::Pdf => "PDF",
+            FileType::Cr2 => "CR2",
+            FileType::Cr3 => "CR3",
+            FileType::Nef => "NEF",
+            FileType::Arw => "ARW",
+            FileType::Dng => "DNG",
+            FileType::Raf => "RAF",
+            FileType::Orf => "ORF",
+            FileType::Rw2 => "RW2",
+            FileType::Mp4 => "MP4",
+            FileType::Mov => "MOV",
+            FileType::Xmp => "XMP",
+            FileType::Other(name) => name,
+        }
+    }
+}
+
+impl From<&str> for FileType {
+    /// Maps the value of exiftool's `FileType` tag to a type.
+    fn from(name: &str) -> Self {
+        match name.trim() {
+            "JPEG" => FileType::Jpeg,
+            "PNG" => FileType::Png,
+            "GIF" => FileType::Gif,
+            "BMP" => FileType::Bmp,
+            "TIFF" => FileType::Tiff,
+            "WEBP" => FileType::WebP,
+            "HEIC" => FileType::Heic,
+            "AVIF" => FileType::Avif,
+            "PSD" => FileType::Psd,
+            "PDF" => FileType::Pdf,
+            "CR2" => FileType::Cr2,
+            "CR3" => FileType::Cr3,
+            "NEF" => FileType::Nef,
+            "ARW" => FileType::Arw,
+            "DNG" => FileType::Dng,
+            "RAF" => FileType::Raf,
+            "ORF" => FileType::Orf,
+            "RW2" => FileType::Rw2,
+            "MP4" => FileType::Mp4,
+            "MOV" => FileType::Mov,
+            "XMP" => FileType::Xmp,
+            name => FileType::Other(name.to_string()),
+        }
+    }
+}
+
+impl ExifTool {
+    /// Detects the format of `file`. Fails with [`ExifToolError::Reported`] if exiftool
+    /// doesn't recognize it.
+    pub async fn file_type(&self, file: &str) -> Result<FileType, ExifToolError> {
+        let tag = self.file_type_tag(file, "FileType").await?;
+        Ok(FileType::from(tag.as_str()))
+    }
+
+    /// Detects the MIME type of `file`, such as `image/jpeg`.
+    pub async fn mime_type(&self, file: &str) -> Result<String, ExifToolError> {
+        self.file_type_tag(file, "MIMEType").await
+    }
+
+    /// Reads one of the tags exiftool derives from the detected file type.
+    async fn file_type_tag(&self, file: &str, tag: &str) -> Result<String, ExifToolError> {
+        let tags: Map<String, Value> = self
+            .execute_struct_one(vec![
+                "-FileType".to_string(),
+                "-MIMEType".to_string(),
+                file.to_string(),
+            ])
+            .await?;
+        match find_tag(&tags, tag).and_then(Value::as_str) {
+            Some(value) => Ok(value.to_string()),
+            None => match find_tag(&tags, "Error").and_then(Value::as_str) {
+                Some(error) => Err(ExifToolError::Reported(error.to_string())),
+                None => Err(ExifToolError::MissingTag(tag.to_string())),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_names() {
+        for file_type in [
+            FileType::Jpeg,
+            FileType::Png,
+            FileType::Gif,
+            FileType::Bmp,
+            FileType::Tiff,
+            FileType::WebP,
+            FileType::Heic,
+            FileType::Avif,
+            FileType::Psd,
+            FileType::Pdf,
+            FileType::Cr2,
+            FileType::Cr3,
+            FileType::Nef,
+            FileType::Arw,
+            FileType::Dng,
+            FileType::Raf,
+            FileType::Orf,
+            FileType::Rw2,
+            FileType::Mp4,
+            FileType::Mov,
+            FileType::Xmp,
+            FileType::Other("MKV".to_string()),
+        ] {
+            assert_eq!(FileType::from(file_type.as_str()), file_type);
+        }
+        assert_eq!(FileType::from("JPEG\n"), FileType::Jpeg);
+        assert_eq!(FileType::from("jpeg"), FileType::Other("jpeg".to_string()));
+    }
+}
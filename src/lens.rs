@@ -0,0 +1,129 @@
+use serde_json::Value;
+
+use crate::{find_tag, ExifTool, ExifToolError};
+
+/// The lens a photo was taken with. Each field is `None` if the file doesn't record it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LensInfo {
+    /// The lens model, such as `EF-S18-55mm f/3.5-5.6 IS STM`.
+    pub model: Option<String>,
+    pub make: Option<String>,
+    /// The shortest focal length in millimetres. For a prime lens, this is its only one.
+    pub min_focal: Option<f64>,
+    /// The longest focal length in millimetres.
+    pub max_focal: Option<f64>,
+    /// The f-number of the widest aperture at the shortest focal length, such as the `3.5`
+    /// of `f/3.5-5.6`.
+    pub min_aperture: Option<f64>,
+    /// The f-number of the widest aperture at the longest focal length, such as the `5.6`
+    /// of `f/3.5-5.6`. For a lens with a constant aperture, this is the same as
+    /// `min_aperture`.
+    pub max_aperture: Option<f64>,
+}
+
+impl ExifTool {
+    /// Reads which lens `file` was taken with, from whichever of `LensModel`, `LensID`,
+    /// `LensMake`, `LensInfo` and `LensSpec` it records, with a single call.
+    ///
+    /// The focal lengths and apertures are taken from `LensInfo` or `LensSpec`, and failing
+    /// those, from a model name in the usual `18-55mm f/3.5-5.6` form.
+    pub async fn lens_info(&self, file: &str) -> Result<LensInfo, ExifToolError> {
+        let tags = self
+            .get_tags_for_file(
+                file,
+                &[
+                    "LensModel",
+                    "LensID",
+                    "Lens",
+                    "LensMake",
+                    "LensInfo",
+                    "LensSpec",
+                ],
+            )
+            .await?;
+        let text = |tag: &str| {
+            find_tag(&tags, tag)
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let model = text("LensModel")
+            .or_else(|| text("LensID"))
+            .or_else(|| text("Lens"));
+
+        let specs = [text("LensInfo"), text("LensSpec"), model.clone()];
+        let parsed: Vec<_> = specs.iter().flatten().map(|s| parse_lens_spec(s)).collect();
+        let focal = parsed.iter().find_map(|(focal, _)| *focal);
+        let aperture = parsed.iter().find_map(|(_, aperture)| *aperture);
+        Ok(LensInfo {
+            model,
+            make: text("LensMake"),
+            min_focal: focal.map(|(min, _)| min),
+            max_focal: focal.map(|(_, max)| max),
+            min_aperture: aperture.map(|(min, _)| min),
+            max_aperture: aperture.map(|(_, max)| max),
+        })
+    }
+}
+
+/// The two ends of a range, which are equal for a single value.
+type Range = (f64, f64);
+
+/// Parses a lens specification such as `18-55mm f/3.5-5.6` or `50mm f/1.8` into its
+/// focal length and aperture ranges. exiftool prints unknown values as `?` or `0`, which
+/// are left out.
+fn parse_lens_spec(spec: &str) -> (Option<Range>, Option<Range>) {
+    let is_range = |c: char| c.is_ascii_digit() || c == '.' || c == '-';
+    let focal = spec.find("mm").and_then(|end| {
+        // Model names run the focal length into the series, as in `EF-S18-55mm`.
+        let start = spec[..end].rfind(|c| !is_range(c)).map_or(0, |pos| pos + 1);
+        parse_range(&spec[start..end])
+    });
+    let aperture = spec.find("f/").and_then(|start| {
+        let rest = &spec[start + 2..];
+        let end = rest.find(|c| !is_range(c)).unwrap_or(rest.len());
+        parse_range(&rest[..end])
+    });
+    (focal, aperture)
+}
+
+/// Parses a range such as `18-55`, or a single value such as `50`, into its two ends.
+fn parse_range(range: &str) -> Option<Range> {
+    let range = range.trim_matches('-');
+    let (min, max) = range.split_once('-').unwrap_or((range, range));
+    let (min, max): (f64, f64) = (min.parse().ok()?, max.parse().ok()?);
+    (min > 0.0 && max > 0.0).then_some((min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lens_spec_zoom_and_prime() {
+        let zoom = parse_lens_spec("18-55mm f/3.5-5.6");
+        assert_eq!(zoom, (Some((18.0, 55.0)), Some((3.5, 5.6))));
+        let prime = parse_lens_spec("50mm f/1.8");
+        assert_eq!(prime, (Some((50.0, 50.0)), Some((1.8, 1.8))));
+    }
+
+    #[test]
+    fn lens_spec_in_model_name() {
+        let model = parse_lens_spec("EF-S18-55mm f/3.5-5.6 IS STM");
+        assert_eq!(model, (Some((18.0, 55.0)), Some((3.5, 5.6))));
+        let model = parse_lens_spec("AF-S DX Nikkor 35mm f/1.8G");
+        assert_eq!(model, (Some((35.0, 35.0)), Some((1.8, 1.8))));
+        // Only the `f/` form is recognised as an aperture.
+        let model = parse_lens_spec("FE 24-105mm F4 G OSS");
+        assert_eq!(model, (Some((24.0, 105.0)), None));
+    }
+
+    #[test]
+    fn lens_spec_unknown_values() {
+        assert_eq!(parse_lens_spec("24-70mm f/?"), (Some((24.0, 70.0)), None));
+        assert_eq!(parse_lens_spec("0-0mm f/0-0"), (None, None));
+        assert_eq!(parse_lens_spec("f/2.8"), (None, Some((2.8, 2.8))));
+        assert_eq!(parse_lens_spec("Unknown lens"), (None, None));
+    }
+}
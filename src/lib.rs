@@ -1,95 +1,1248 @@
-use std::io::{Read, Write};
-use std::process::{Child, Stdio};
+use std::borrow::Cow;
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "async")]
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fmt;
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+#[cfg(feature = "async")]
+use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
 use std::str::FromStr;
+#[cfg(feature = "async")]
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+#[cfg(feature = "async")]
+use std::sync::Arc;
 use std::time::Duration;
 
+use base64::prelude::{Engine, BASE64_STANDARD};
 use bstr::ByteSlice;
+use serde::de::DeserializeOwned;
+#[cfg(feature = "async")]
+use serde::de::{MapAccess, Visitor};
+#[cfg(feature = "async")]
+use serde::Deserialize;
+#[cfg(feature = "async")]
+use serde::Serialize;
+#[cfg(feature = "async")]
+use serde_json::Map;
 use serde_json::Value;
-use tokio::sync::Mutex;
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+#[cfg(feature = "async")]
+use tokio::process::Child;
+#[cfg(feature = "async")]
+use tokio::sync::{Mutex, MutexGuard};
 
+#[cfg(not(any(feature = "async", feature = "sync")))]
+compile_error!("exiftool needs at least one of its `async` or `sync` features");
+
+#[cfg(feature = "async")]
+mod api;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+#[cfg(all(feature = "chrono", feature = "async"))]
+mod datetime;
+#[cfg(feature = "async")]
+mod dimensions;
+#[cfg(feature = "async")]
+mod exposure;
+#[cfg(feature = "async")]
+mod file_type;
+#[cfg(feature = "async")]
+mod lens;
+#[cfg(feature = "async")]
+mod pool;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+mod summary;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "async")]
+mod thumbnail;
+#[cfg(feature = "async")]
+mod time_shift;
+#[cfg(feature = "async")]
+mod validate;
+
+#[cfg(feature = "async")]
+pub use api::ExifToolApi;
+#[cfg(all(feature = "chrono", feature = "async"))]
+pub use datetime::CaptureDateTime;
+#[cfg(feature = "async")]
+pub use exposure::ExposureSettings;
+#[cfg(feature = "async")]
+pub use file_type::FileType;
+#[cfg(feature = "async")]
+pub use lens::LensInfo;
+#[cfg(feature = "async")]
+pub use pool::{ExifToolPool, PooledExifTool};
+#[cfg(feature = "async")]
+pub use summary::ImageSummary;
+#[cfg(feature = "async")]
+pub use thumbnail::ThumbnailInfo;
+#[cfg(feature = "async")]
+pub use time_shift::TimeShift;
+#[cfg(feature = "async")]
+pub use tokio_util::sync::CancellationToken;
+#[cfg(feature = "async")]
+pub use validate::{ValidationIssue, ValidationReport};
+
+/// Whether `c` is whitespace, including both halves of the `\r\n` line endings exiftool
+/// prints on Windows.
 fn is_whitespace(c: &u8) -> bool {
-    c == &b'\t' || c == &b' '
+    matches!(c, b'\t' | b' ' | b'\r' | b'\n')
 }
 
 fn is_not_whitespace(c: &u8) -> bool {
     !is_whitespace(c)
 }
 
+/// Strips trailing whitespace, such as the line ending after a marker, from `v`.
 fn trim_end(v: &mut Vec<u8>) {
-    if let Some(first) = v.iter().rposition(is_not_whitespace) {
-        v.truncate(first);
-    } else {
-        v.truncate(0);
+    match v.iter().rposition(is_not_whitespace) {
+        Some(last) => v.truncate(last + 1),
+        None => v.clear(),
     }
 }
 
 const SEQ_ERR_STATUS_DELIM: &str = "=";
 
-async fn read_fd_ends_with<R: Read>(mut fd: R, seq_ready: &str, block_size: usize) -> Vec<u8> {
-    let endswith_count = seq_ready.bytes().len() + 2;
+/// Joins `args` into a line which a POSIX shell would split back into them, quoting those
+/// which need it.
+fn shell_words(args: &[String]) -> String {
+    let quote = |arg: &String| {
+        let plain = |c: char| c.is_ascii_alphanumeric() || "-_./:=,+@%#".contains(c);
+        if !arg.is_empty() && arg.chars().all(plain) {
+            arg.clone()
+        } else {
+            format!("'{}'", arg.replace('\'', "'\\''"))
+        }
+    };
+    args.iter().map(quote).collect::<Vec<_>>().join(" ")
+}
+
+/// Looks up `tag` in the tags read from a file, whether or not exiftool qualified its name
+/// with a group, as it does under a [`GroupMode`] or with
+/// [`ExifToolBuilder::allow_duplicates`]. If several groups have the tag, the first in
+/// the map is taken.
+#[cfg(feature = "async")]
+fn find_tag<'a>(tags: &'a Map<String, Value>, tag: &str) -> Option<&'a Value> {
+    tags.get(tag).or_else(|| {
+        tags.iter()
+            .find(|(key, _)| key.rsplit(':').next().is_some_and(|name| name == tag))
+            .map(|(_, value)| value)
+    })
+}
+
+/// Reads a numeric tag value, which exiftool may print as either a JSON number or string.
+#[cfg(feature = "async")]
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Parses a rational tag value, which exiftool prints as a fraction such as `1/200` or
+/// `-1/3`, or as a whole or decimal number such as `300` or `5.6`. Decimals are read as the
+/// exact fraction they spell out, so `5.6` is `(56, 10)`. Fractions are not reduced.
+#[cfg(feature = "async")]
+fn parse_rational(value: &Value) -> Option<(i64, i64)> {
+    let text = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        _ => return None,
+    };
+    let text = text.strip_prefix('+').unwrap_or(&text);
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        let denominator: i64 = denominator.trim().parse().ok()?;
+        if denominator == 0 {
+            return None;
+        }
+        return Some((numerator.trim().parse().ok()?, denominator));
+    }
+    let (whole, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let denominator = 10i64.checked_pow(fraction.len().try_into().ok()?)?;
+    let numerator: i64 = format!("{whole}{fraction}").parse().ok()?;
+    Some((numerator, denominator))
+}
+
+/// Checks whether `bytes` start like an image in one of the formats cameras embed as
+/// previews and thumbnails: JPEG, PNG, TIFF, GIF, BMP, WebP or HEIF.
+pub fn looks_like_image(bytes: &[u8]) -> bool {
+    const MAGIC: &[&[u8]] = &[
+        b"\xFF\xD8\xFF",
+        b"\x89PNG\r\n\x1A\n",
+        b"II*\0",
+        b"MM\0*",
+        b"GIF8",
+        b"BM",
+    ];
+    MAGIC.iter().any(|magic| bytes.starts_with(magic))
+        || (bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP"))
+        || bytes.get(4..8) == Some(b"ftyp")
+}
+
+/// Decodes a binary tag value from JSON output read with `-b`, which exiftool prints as a
+/// `base64:` prefixed string. Returns `None` for any other value.
+#[cfg_attr(feature = "async", doc = "See [`ExifTool::execute_json_binary`].")]
+pub fn decode_base64_tag(value: &Value) -> Option<Vec<u8>> {
+    let encoded = value.as_str()?.strip_prefix("base64:")?;
+    BASE64_STANDARD.decode(encoded).ok()
+}
+
+#[derive(Debug)]
+pub enum ExifToolError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// exiftool's output was not the expected JSON. Carries both streams for debugging.
+    InvalidJson {
+        source: serde_json::Error,
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+    /// The `${status}` block on stderr could not be parsed. Carries the offending bytes.
+    StatusParse(Vec<u8>),
+    MissingReadyMarker,
+    ProcessGone,
+    /// The exiftool process has exited, in the way given.
+    Exited(ProcessExit),
+    Timeout,
+    /// A fatal error which exiftool reported, such as the `Error` tag of a file it could not
+    /// read. Unlike [`ExifToolError::MinorError`], retrying with
+    /// [`ExifToolBuilder::ignore_minor_errors`] won't get past it.
+    Reported(String),
+    /// exiftool stopped because of a minor error, such as a slightly malformed metadata
+    /// block, which [`ExifToolBuilder::ignore_minor_errors`] would let it carry on past.
+    /// Every other error exiftool reports is fatal, and is an [`ExifToolError::Reported`].
+    MinorError(String),
+    /// exiftool could not find a file it was asked to read or write.
+    FileNotFound(PathBuf),
+    /// An argument can't be passed to exiftool, for the given reason.
+    InvalidArgument(String),
+    /// A tag's value was not in the format exiftool normally prints it in.
+    InvalidValue {
+        tag: String,
+        value: String,
+    },
+    /// The requested tag is not present in the file.
+    MissingTag(String),
+    /// A write succeeded, but reading the tag back gave `value` rather than what was
+    /// written. Unlike other errors from writes, this means the file was modified.
+    Unverified {
+        tag: String,
+        value: String,
+    },
+    /// The requested binary tag is present in the file, but holds no data, or only
+    /// zero-filled padding where an image was expected.
+    EmptyBinary(String),
+    /// exiftool is older than the minimum version required.
+    UnsupportedVersion {
+        found: (u32, u32),
+        required: (u32, u32),
+    },
+    /// A single result was requested, but exiftool returned this many.
+    UnexpectedResultCount(usize),
+    /// A previous call timed out, or failed partway through reading its response, so output
+    /// left in the pipes could be mistaken for a later response.
+    #[cfg_attr(
+        feature = "async",
+        doc = "Cleared by [`ExifTool::drain`] or [`ExifTool::reset`]."
+    )]
+    #[cfg_attr(not(feature = "async"), doc = "Cleared by [`sync::ExifTool::reset`].")]
+    Poisoned,
+    /// A file could not be renamed because its new name, given here, is already taken.
+    AlreadyExists(String),
+    /// The call was cancelled.
+    #[cfg_attr(feature = "async", doc = "See [`ExifTool::execute_with_cancel`].")]
+    Cancelled,
+    SpawnFailed {
+        path: String,
+        source: io::Error,
+    },
+}
+
+impl fmt::Display for ExifToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExifToolError::Io(e) => write!(f, "exiftool I/O error: {e}"),
+            ExifToolError::Json(e) => write!(f, "could not parse exiftool JSON output: {e}"),
+            ExifToolError::InvalidJson {
+                source,
+                stdout,
+                stderr,
+            } => write!(
+                f,
+                "could not parse exiftool JSON output ({source}), stdout: {:?}, stderr: {:?}",
+                stdout.as_bstr(),
+                stderr.as_bstr()
+            ),
+            ExifToolError::StatusParse(bytes) => write!(
+                f,
+                "could not parse exiftool status from {:?}",
+                bytes.as_bstr()
+            ),
+            ExifToolError::MissingReadyMarker => {
+                write!(f, "exiftool output did not end with the expected marker")
+            }
+            ExifToolError::ProcessGone => write!(f, "exiftool process is no longer running"),
+            ExifToolError::Exited(ProcessExit::Code(code)) => {
+                write!(f, "exiftool process exited with status {code}")
+            }
+            ExifToolError::Exited(ProcessExit::Signal(signal)) => {
+                write!(f, "exiftool process was terminated by signal {signal}")
+            }
+            ExifToolError::Timeout => write!(f, "exiftool did not respond in time"),
+            ExifToolError::Reported(message) => write!(f, "exiftool reported an error: {message}"),
+            ExifToolError::MinorError(message) => {
+                write!(f, "exiftool reported a minor error: {message}")
+            }
+            ExifToolError::FileNotFound(path) => write!(f, "file not found: {}", path.display()),
+            ExifToolError::InvalidArgument(reason) => write!(f, "invalid argument: {reason}"),
+            ExifToolError::InvalidValue { tag, value } => {
+                write!(f, "could not parse the value {value:?} of the {tag} tag")
+            }
+            ExifToolError::MissingTag(tag) => write!(f, "file does not contain the {tag} tag"),
+            ExifToolError::Unverified { tag, value } => {
+                write!(f, "the {tag} tag was written, but reads back as {value:?}")
+            }
+            ExifToolError::EmptyBinary(tag) => write!(f, "the {tag} tag of the file is empty"),
+            ExifToolError::UnsupportedVersion { found, required } => write!(
+                f,
+                "exiftool {}.{:02} is installed, but {}.{:02} or newer is required",
+                found.0, found.1, required.0, required.1
+            ),
+            ExifToolError::UnexpectedResultCount(n) => {
+                write!(
+                    f,
+                    "expected exiftool to return one result, but it returned {n}"
+                )
+            }
+            ExifToolError::Poisoned => write!(
+                f,
+                "exiftool process is unusable because a previous call was interrupted"
+            ),
+            ExifToolError::AlreadyExists(path) => write!(f, "{path:?} already exists"),
+            ExifToolError::Cancelled => write!(f, "exiftool call was cancelled"),
+            ExifToolError::SpawnFailed { path, source } => write!(
+                f,
+                "could not spawn exiftool at {path:?} (is exiftool installed?): {source}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ExifToolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExifToolError::Io(e) => Some(e),
+            ExifToolError::Json(e) => Some(e),
+            ExifToolError::InvalidJson { source, .. } => Some(source),
+            ExifToolError::SpawnFailed { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExifToolError {
+    fn from(e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::BrokenPipe {
+            ExifToolError::ProcessGone
+        } else {
+            ExifToolError::Io(e)
+        }
+    }
+}
+
+impl From<serde_json::Error> for ExifToolError {
+    fn from(e: serde_json::Error) -> Self {
+        ExifToolError::Json(e)
+    }
+}
+
+/// How a process ended, as read from its [`ExitStatus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessExit {
+    /// The process exited by itself, with this status code.
+    Code(i32),
+    /// The process was terminated by this signal. Only Unix processes end this way.
+    Signal(i32),
+}
+
+impl From<ExitStatus> for ProcessExit {
+    fn from(status: ExitStatus) -> Self {
+        match status.code() {
+            Some(code) => ProcessExit::Code(code),
+            #[cfg(unix)]
+            None => ProcessExit::Signal(
+                std::os::unix::process::ExitStatusExt::signal(&status).unwrap_or_default(),
+            ),
+            // Processes on other platforms always exit with a code.
+            #[cfg(not(unix))]
+            None => ProcessExit::Code(-1),
+        }
+    }
+}
+
+/// Returns the position of `marker` in `output`, once the newline exiftool prints after it
+/// has arrived too. The marker needn't be last, as exiftool can print diagnostics after it,
+/// which belong to the same response.
+fn find_end_marker(output: &[u8], marker: &str) -> Option<usize> {
+    output.find_iter(marker).find(|&pos| {
+        let rest = &output[pos + marker.len()..];
+        rest.starts_with(b"\n") || rest.starts_with(b"\r\n")
+    })
+}
+
+/// Where to start looking for `marker` in output which has grown past `len` bytes, so that
+/// each read only searches what it added, along with a marker split across reads.
+fn marker_search_start(len: usize, marker: &str) -> usize {
+    len.saturating_sub(marker.len() + 1)
+}
+
+#[cfg(feature = "async")]
+async fn read_fd_ends_with<R: AsyncRead + Unpin>(
+    mut fd: R,
+    seq_ready: &str,
+    block_size: usize,
+) -> Result<Vec<u8>, ExifToolError> {
     let mut output = Vec::new();
     let mut buf = vec![0; block_size];
     loop {
-        match fd.read(&mut buf) {
-            Ok(n) => {
-                if n == 0 {
-                    tokio::time::sleep(Duration::from_millis(10)).await;
-                }
-                output.extend_from_slice(&buf[..n]);
-                if output[output.len().saturating_sub(endswith_count)..]
-                    .find(seq_ready.as_bytes())
-                    .is_some()
-                {
-                    break;
+        let n = fd.read(&mut buf).await?;
+        if n == 0 {
+            // The pipe only reaches EOF once exiftool has exited.
+            return Err(ExifToolError::ProcessGone);
+        }
+        let start = marker_search_start(output.len(), seq_ready);
+        output.extend_from_slice(&buf[..n]);
+        // Stopping before the trailing newline would leave it to be read as part of the
+        // next response.
+        if find_end_marker(&output[start..], seq_ready).is_some() {
+            break;
+        }
+    }
+    Ok(output)
+}
+
+/// Like `read_fd_ends_with`, but writes everything before `seq_ready` to `writer` as it
+/// arrives rather than buffering it. Returns the number of bytes written.
+#[cfg(feature = "async")]
+async fn stream_fd_ends_with<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    mut fd: R,
+    seq_ready: &str,
+    block_size: usize,
+    writer: &mut W,
+) -> Result<u64, ExifToolError> {
+    // The marker may be split across reads, so the tail is held back until more arrives.
+    let endswith_count = seq_ready.len() + 2;
+    let mut pending = Vec::new();
+    let mut written = 0;
+    let mut buf = vec![0; block_size];
+    loop {
+        let n = fd.read(&mut buf).await?;
+        if n == 0 {
+            return Err(ExifToolError::ProcessGone);
+        }
+        pending.extend_from_slice(&buf[..n]);
+        if let Some(pos) = find_end_marker(&pending, seq_ready) {
+            writer.write_all(&pending[..pos]).await?;
+            writer.flush().await?;
+            return Ok(written + pos as u64);
+        }
+        let tail = pending.len().saturating_sub(endswith_count);
+        writer.write_all(&pending[..tail]).await?;
+        written += tail as u64;
+        pending.drain(..tail);
+    }
+}
+
+/// Parses the `major.minor` version printed by `exiftool -ver`.
+fn parse_version(output: &[u8]) -> Option<(u32, u32)> {
+    let (major, minor) = output.trim().to_str().ok()?.split_once('.')?;
+    // Development releases, such as 12.65-dev, carry a suffix.
+    let minor = minor.split(|c: char| !c.is_ascii_digit()).next()?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// What the running exiftool supports, as detected from `exiftool -ver`.
+#[derive(Debug, Clone, Copy)]
+struct Capabilities {
+    /// `None` if the version couldn't be parsed.
+    version: Option<(u32, u32)>,
+    /// Whether `${status}` can be echoed, which exiftool supports from 12.10. Without it,
+    /// the status is inferred from whether stderr contains an error.
+    status: bool,
+}
+
+impl Capabilities {
+    fn detect(version_output: &[u8]) -> Self {
+        let version = parse_version(version_output);
+        Capabilities {
+            version,
+            status: version.is_some_and(|version| version >= (12, 10)),
+        }
+    }
+}
+
+/// The `SourceFile` exiftool reports for `path`, which has forward slashes, even on Windows.
+#[cfg(feature = "async")]
+fn source_file(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Formats a serialized field as a value for `-TAG=VALUE`.
+#[cfg(feature = "async")]
+fn tag_value(tag: &str, value: Value) -> Result<String, ExifToolError> {
+    match value {
+        Value::String(s) => Ok(s),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        _ => Err(ExifToolError::InvalidArgument(format!(
+            "the value of {tag} can't be written as a tag"
+        ))),
+    }
+}
+
+/// Reads the GPS position from the numeric `GPSLatitude` and `GPSLongitude` tags and their
+/// `Ref` tags, in signed decimal degrees.
+#[cfg(feature = "async")]
+fn gps_from_tags(tags: &Map<String, Value>) -> Option<(f64, f64)> {
+    // The EXIF coordinates are unsigned, with the hemisphere given by the Ref tags.
+    let coordinate = |tag: &str, ref_tag: &str, negative_ref: char| {
+        let value = find_tag(tags, tag).and_then(value_as_f64)?;
+        match find_tag(tags, ref_tag).and_then(Value::as_str) {
+            Some(r) if r.starts_with(negative_ref) => Some(-value.abs()),
+            Some(_) => Some(value.abs()),
+            None => Some(value),
+        }
+    };
+    coordinate("GPSLatitude", "GPSLatitudeRef", 'S').zip(coordinate(
+        "GPSLongitude",
+        "GPSLongitudeRef",
+        'W',
+    ))
+}
+
+/// Strips the whitespace between JSON tokens, leaving strings untouched.
+#[cfg(feature = "async")]
+fn compact_json(json: &str) -> String {
+    let mut compact = String::with_capacity(json.len());
+    let (mut in_string, mut escaped) = (false, false);
+    for c in json.chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c.is_ascii_whitespace() {
+            continue;
+        }
+        compact.push(c);
+    }
+    compact
+}
+
+/// A JSON object's entries in the order they appeared in, whether or not serde_json's
+/// `preserve_order` feature is enabled.
+#[cfg(feature = "async")]
+struct OrderedObject(Vec<(String, Value)>);
+
+#[cfg(feature = "async")]
+impl<'de> Deserialize<'de> for OrderedObject {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OrderedVisitor;
+
+        impl<'de> Visitor<'de> for OrderedVisitor {
+            type Value = OrderedObject;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OrderedObject, A::Error> {
+                let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
                 }
+                Ok(OrderedObject(entries))
+            }
+        }
+
+        deserializer.deserialize_map(OrderedVisitor)
+    }
+}
+
+/// The markers which delimit exiftool's response to a single command.
+struct Markers {
+    /// Printed on stdout once exiftool has finished the command.
+    ready: String,
+    /// Printed on stderr after the `${status}` block.
+    err_post: String,
+    /// Whether the `${status}` block is echoed. exiftool only supports it from 12.10.
+    with_status: bool,
+}
+
+#[cfg(feature = "async")]
+async fn send(process: &mut Child, message: &[u8]) -> Result<(), ExifToolError> {
+    let stdin = process.stdin.as_mut().ok_or(ExifToolError::ProcessGone)?;
+    stdin.write_all(message).await?;
+    stdin.flush().await?;
+    Ok(())
+}
+
+/// Sends `message` to exiftool and reads stdout and stderr up to their respective markers.
+#[cfg(feature = "async")]
+async fn communicate(
+    process: &mut Child,
+    message: &[u8],
+    markers: &Markers,
+    block_size: usize,
+) -> Result<(Vec<u8>, Vec<u8>), ExifToolError> {
+    let response = async {
+        send(process, message).await?;
+
+        // Both pipes are read concurrently so that a full stderr can't block stdout.
+        let stdout = process.stdout.as_mut().ok_or(ExifToolError::ProcessGone)?;
+        let stderr = process.stderr.as_mut().ok_or(ExifToolError::ProcessGone)?;
+        tokio::try_join!(
+            read_fd_ends_with(stdout, &markers.ready, block_size),
+            read_fd_ends_with(stderr, &markers.err_post, block_size),
+        )
+    }
+    .await;
+    reap_if_gone(process, response).await
+}
+
+/// If exiftool closed its pipes mid-response, makes sure it has exited and been reaped, so
+/// that the next call reliably sees it as gone rather than writing to a dying process.
+#[cfg(feature = "async")]
+async fn reap_if_gone<T>(
+    process: &mut Child,
+    response: Result<T, ExifToolError>,
+) -> Result<T, ExifToolError> {
+    if matches!(response, Err(ExifToolError::ProcessGone)) {
+        let _ = process.kill().await;
+    }
+    response
+}
+
+/// Runs `attempt` up to `max_attempts` times while it fails with an error that may not
+/// recur, waiting `backoff`, then twice as long each time, and calling `reset` between
+/// attempts. With `retry` unset, `attempt` runs once.
+#[cfg(feature = "async")]
+async fn retry_transient<T, A, R>(
+    retry: Option<(u32, Duration)>,
+    mut attempt: impl FnMut() -> A,
+    mut reset: impl FnMut() -> R,
+) -> Result<T, ExifToolError>
+where
+    A: Future<Output = Result<T, ExifToolError>>,
+    R: Future<Output = Result<(), ExifToolError>>,
+{
+    let (max_attempts, mut backoff) = retry.unwrap_or((1, Duration::ZERO));
+    let mut attempts = 1;
+    loop {
+        match attempt().await {
+            Err(ExifToolError::Io(_) | ExifToolError::Timeout | ExifToolError::ProcessGone)
+                if attempts < max_attempts =>
+            {
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+                attempts += 1;
+                reset().await?;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Strips the `{ready}` marker from the end of stdout.
+fn parse_stdout(mut raw_stdout: Vec<u8>, markers: &Markers) -> Result<Vec<u8>, ExifToolError> {
+    trim_end(&mut raw_stdout);
+    if !raw_stdout.ends_with(markers.ready.as_bytes()) {
+        return Err(ExifToolError::MissingReadyMarker);
+    }
+    raw_stdout.truncate(raw_stdout.len() - markers.ready.len());
+    Ok(raw_stdout)
+}
+
+/// Strips the `${status}` block from stderr, returning the status and the remaining
+/// diagnostics.
+///
+/// exiftool only sets statuses 0, 1 and 2, but any which fits an `i32` is accepted. Larger
+/// ones fail with [`ExifToolError::StatusParse`].
+fn parse_stderr(
+    mut raw_stderr: Vec<u8>,
+    markers: &Markers,
+) -> Result<(i32, Vec<u8>), ExifToolError> {
+    trim_end(&mut raw_stderr);
+    // The block is normally last, but anything exiftool printed after it, and which arrived
+    // along with it, is kept with the rest of the diagnostics.
+    let post = match raw_stderr.rfind(markers.err_post.as_bytes()) {
+        Some(post) => post,
+        None => return Err(ExifToolError::MissingReadyMarker),
+    };
+    let after = raw_stderr.split_off(post).split_off(markers.err_post.len());
+    let after = after.trim_start_with(|c| c == '\n' || c == '\r');
+    let with_after = |mut diagnostics: Vec<u8>| {
+        if !after.is_empty() {
+            if !diagnostics.is_empty() && !diagnostics.ends_with(b"\n") {
+                diagnostics.push(b'\n');
+            }
+            diagnostics.extend_from_slice(after);
+        }
+        diagnostics
+    };
+
+    if !markers.with_status {
+        // Without ${status}, an error message is the only sign of failure.
+        let raw_stderr = with_after(raw_stderr);
+        let status = i32::from(raw_stderr.lines().any(|line| line.starts_with(b"Error")));
+        return Ok((status, raw_stderr));
+    }
+
+    // The block is a line of its own, `=<digits>=`, so it is matched exactly rather than by
+    // the nearest `=`, which could belong to a warning.
+    let delim = SEQ_ERR_STATUS_DELIM.as_bytes();
+    let body = match raw_stderr.strip_suffix(delim) {
+        Some(body) => body,
+        None => return Err(ExifToolError::StatusParse(raw_stderr)),
+    };
+    let digits_start = body
+        .iter()
+        .rposition(|b| !b.is_ascii_digit())
+        .map_or(0, |pos| pos + 1);
+    let next_delim = match body[..digits_start].strip_suffix(delim) {
+        Some(line) if line.is_empty() || line.ends_with(b"\n") => line.len(),
+        _ => return Err(ExifToolError::StatusParse(raw_stderr)),
+    };
+    let status_code = &body[digits_start..];
+    let status_code = match status_code
+        .to_str()
+        .ok()
+        .and_then(|s| i32::from_str(s).ok())
+    {
+        Some(status_code) => status_code,
+        None => return Err(ExifToolError::StatusParse(status_code.to_vec())),
+    };
+    raw_stderr.truncate(next_delim);
+    Ok((status_code, with_after(raw_stderr)))
+}
+
+/// Turns the stdout and stderr of a response, each read up to its marker, into an
+/// [`ExifToolOutput`]. This is all of a call which doesn't need the process, so that it
+/// can be checked against any bytes.
+fn parse_response(
+    raw_stdout: Vec<u8>,
+    raw_stderr: Vec<u8>,
+    markers: &Markers,
+) -> Result<ExifToolOutput, ExifToolError> {
+    let raw_stdout = parse_stdout(raw_stdout, markers)?;
+    let (status_code, raw_stderr) = parse_stderr(raw_stderr, markers)?;
+    Ok(ExifToolOutput::new(status_code, raw_stdout, raw_stderr))
+}
+
+/// The first marker number of a process. Starting at a random value means that a marker
+/// can't be guessed, such as by a file crafted to print it.
+fn initial_signal_num() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+const DEFAULT_READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// How long [`ExifTool::ping`] waits for exiftool to respond.
+#[cfg(feature = "async")]
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long to wait for exiftool to exit after asking it to before killing it.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Asks a `-stay_open` exiftool process to exit, by sending it `-stay_open False` and
+/// closing its stdin. Returns whether it had already exited.
+///
+/// This is synchronous so that it can be used from `Drop`. The pipe is non-blocking, so the
+/// command is only written if it fits, as it always does for an idle process, but closing
+/// stdin also makes exiftool exit once it has finished whatever it was doing.
+#[cfg(feature = "async")]
+fn request_exit(process: &mut Child) -> io::Result<bool> {
+    if process.try_wait()?.is_some() {
+        return Ok(true);
+    }
+    if let Some(stdin) = process.stdin.take() {
+        #[cfg(unix)]
+        let pipe = std::os::fd::AsFd::as_fd(&stdin).try_clone_to_owned()?;
+        #[cfg(windows)]
+        let pipe = std::os::windows::io::AsHandle::as_handle(&stdin).try_clone_to_owned()?;
+        let mut pipe = std::fs::File::from(pipe);
+        // The process may have died since try_wait, so a broken pipe is expected.
+        match io::Write::write_all(&mut pipe, b"-stay_open\nFalse\n") {
+            Err(e)
+                if !matches!(
+                    e.kind(),
+                    io::ErrorKind::BrokenPipe | io::ErrorKind::WouldBlock
+                ) =>
+            {
+                return Err(e)
             }
-            Err(_) => break,
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+/// Waits for a process which was asked to exit, killing it if it does not within
+/// [`SHUTDOWN_TIMEOUT`].
+#[cfg(feature = "async")]
+async fn wait_or_kill(process: &mut Child) -> io::Result<()> {
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, process.wait())
+        .await
+        .is_err()
+    {
+        process.kill().await?;
+    }
+    Ok(())
+}
+
+/// Like `wait_or_kill`, but blocks the thread, for when there is no runtime to wait on.
+#[cfg(feature = "async")]
+fn wait_or_kill_blocking(process: &mut Child) -> io::Result<()> {
+    let start = std::time::Instant::now();
+    while start.elapsed() < SHUTDOWN_TIMEOUT {
+        if process.try_wait()?.is_some() {
+            return Ok(());
         }
+        std::thread::sleep(Duration::from_millis(10));
     }
-    output
+    process.start_kill()
 }
 
+#[cfg(feature = "async")]
 pub struct ExifTool {
-    process: Mutex<Child>,
+    /// Only dropped by hand, in `Drop`, which moves the process out to shut it down.
+    process: ManuallyDrop<Mutex<Child>>,
+    config: ExifToolBuilder,
+    /// Set while a call is reading its response, and left set if it doesn't finish, since
+    /// that can leave unread bytes in the pipes.
+    poisoned: AtomicBool,
+    /// Set when a call is cancelled partway through its response, which is left in the
+    /// pipes for the next call to drain before the process can be used again.
+    cancelled: AtomicBool,
+    /// Embedded in the markers of each command so that they can't be mistaken for output.
+    /// Starts at a random value and increases with every call.
+    signal_num: AtomicU64,
+    /// Detected on first use, and again after the process is respawned.
+    capabilities: std::sync::Mutex<Option<Capabilities>>,
+    /// The arguments of the most recent call, for [`ExifTool::last_command`].
+    last_command: std::sync::Mutex<Vec<String>>,
+    /// The file extensions exiftool supports, keyed by the option which lists them.
+    extensions: std::sync::Mutex<HashMap<&'static str, Vec<String>>>,
 }
 
-pub struct ExifToolOutput {
-    pub status: u8,
-    pub output: Vec<u8>,
-    pub error: Vec<u8>,
+/// What a write does with the original file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackupMode {
+    /// Keeps the unmodified file with an `_original` suffix. This is the safest mode, but
+    /// costs a copy of every file written.
+    #[default]
+    Backup,
+    /// Writes a new file in place of the original, via `-overwrite_original`. The new file
+    /// is only swapped in once it has been written in full, but it has a new inode, so
+    /// hard links, and ownership or permissions which can't be copied, are lost.
+    Overwrite,
+    /// Rewrites the original file itself, via `-overwrite_original_in_place`, which keeps
+    /// its inode, ownership, permissions and other attributes, such as on network shares.
+    /// This is slower, as the file is written twice, and an interrupted write can leave
+    /// it corrupt.
+    OverwriteInPlace,
 }
 
-impl ExifTool {
+impl BackupMode {
+    /// The exiftool argument for this mode, if any.
+    pub fn arg(self) -> Option<&'static str> {
+        match self {
+            BackupMode::Backup => None,
+            BackupMode::Overwrite => Some("-overwrite_original"),
+            BackupMode::OverwriteInPlace => Some("-overwrite_original_in_place"),
+        }
+    }
+}
+
+/// Whether, and how, tag names in JSON output are qualified with the group they belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupMode {
+    /// Plain tag names, such as `Make`.
+    #[default]
+    None,
+    /// The family 0 (general location) group, such as `EXIF:Make`.
+    Group0,
+    /// The family 1 (specific location) group, such as `IFD0:Make`.
+    Group1,
+    /// Both family 0 and 1 groups, such as `EXIF:IFD0:Make`.
+    Combined,
+}
+
+impl GroupMode {
+    /// The exiftool argument which selects this mode, if any. This can be passed in the
+    /// params of a single call to override the builder's mode.
+    pub fn arg(self) -> Option<&'static str> {
+        match self {
+            GroupMode::None => None,
+            GroupMode::Group0 => Some("-G0"),
+            GroupMode::Group1 => Some("-G1"),
+            GroupMode::Combined => Some("-G0:1"),
+        }
+    }
+}
+
+/// How much of each file exiftool scans for metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScanSpeed {
+    /// Scan the whole file.
+    #[default]
+    Full,
+    /// `-fast`: stop before the trailing part of the file, such as JPEG trailers. This can
+    /// miss tags stored there.
+    Fast,
+    /// `-fast2`: as `Fast`, and also skip maker notes, so no maker note tags are read.
+    Fast2,
+}
+
+impl ScanSpeed {
+    /// The exiftool argument which selects this speed, if any. This can be passed in the
+    /// params of a single call to override the builder's speed.
+    pub fn arg(self) -> Option<&'static str> {
+        match self {
+            ScanSpeed::Full => None,
+            ScanSpeed::Fast => Some("-fast"),
+            ScanSpeed::Fast2 => Some("-fast2"),
+        }
+    }
+}
+
+/// Configures and spawns an [`ExifTool`].
+#[cfg_attr(not(feature = "async"), doc = "")]
+#[cfg_attr(not(feature = "async"), doc = "[`ExifTool`]: sync::ExifTool")]
+#[derive(Debug, Clone, Default)]
+pub struct ExifToolBuilder {
+    binary: Option<PathBuf>,
+    config_file: Option<PathBuf>,
+    current_dir: Option<PathBuf>,
+    env: Vec<(OsString, OsString)>,
+    env_clear: bool,
+    args: Vec<String>,
+    charset: Option<String>,
+    filename_charset: Option<String>,
+    group_mode: GroupMode,
+    scan_speed: ScanSpeed,
+    numeric: bool,
+    extract_embedded: bool,
+    structured: bool,
+    allow_duplicates: bool,
+    ignore_minor_errors: bool,
+    api_options: Vec<(String, String)>,
+    auto_restart: bool,
+    /// How many attempts a read gets, and how long to wait before the first retry.
+    #[cfg(feature = "async")]
+    retry: Option<(u32, Duration)>,
+    /// How long a read may take before it fails with a timeout, and may be retried.
+    #[cfg(feature = "async")]
+    read_timeout: Option<Duration>,
+    min_version: Option<(u32, u32)>,
+    read_buffer_size: Option<usize>,
+}
+
+impl ExifToolBuilder {
     pub fn new() -> Self {
-        let process =
-            std::process::Command::new(std::env::var("EXIFTOOL").unwrap_or("exiftool".to_string()))
-                .args(["-stay_open", "True", "-@", "-"])
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .unwrap();
-        ExifTool {
-            process: Mutex::new(process),
+        Self::default()
+    }
+
+    /// Sets the exiftool binary to run. Defaults to the `EXIFTOOL` environment variable,
+    /// or `exiftool` on `PATH` if it is unset.
+    pub fn binary(mut self, binary: impl Into<PathBuf>) -> Self {
+        self.binary = Some(binary.into());
+        self
+    }
+
+    /// Loads an exiftool config file, which can define user tags and composite tags.
+    ///
+    /// exiftool only accepts `-config` as the very first argument on its command line,
+    /// which is read once at startup. So it is passed when the process is spawned, and
+    /// can't be given through [`ExifToolBuilder::arg`] or the params of a call.
+    pub fn config_file(mut self, config_file: impl Into<PathBuf>) -> Self {
+        self.config_file = Some(config_file.into());
+        self
+    }
+
+    /// Sets the working directory exiftool is spawned in. Relative paths in the params of
+    /// every call, including those exiftool writes to with `-o` or `-w`, and a relative
+    /// [`ExifToolBuilder::config_file`], are then resolved against it rather than against
+    /// this process's working directory. Paths exiftool prints, such as `SourceFile`, stay
+    /// relative as they were given.
+    ///
+    /// The directory is fixed when the process is spawned, so later changes to this
+    /// process's working directory don't affect it.
+    pub fn current_dir(mut self, current_dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(current_dir.into());
+        self
+    }
+
+    /// Sets an environment variable for exiftool, such as `EXIFTOOL_HOME` to choose the
+    /// directory its `.ExifTool_config` is loaded from. Otherwise exiftool inherits this
+    /// process's environment.
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Spawns exiftool with an empty environment, apart from the variables set with
+    /// [`ExifToolBuilder::env`], so that variables such as `PERL5LIB` can't change how it
+    /// behaves. The exiftool script finds Perl through `PATH`, so it may need to be set
+    /// again.
+    pub fn env_clear(mut self) -> Self {
+        self.env_clear = true;
+        self.env.clear();
+        self
+    }
+
+    /// Adds an argument which is passed to exiftool on every call. Like the other options
+    /// set here, it is given once, after `-common_args` on exiftool's command line, and
+    /// exiftool applies it to each command it then reads.
+    pub fn arg(mut self, arg: String) -> Self {
+        self.args.push(arg);
+        self
+    }
+
+    /// Adds several arguments which are passed to exiftool on every call, as with
+    /// [`ExifToolBuilder::arg`].
+    pub fn args(mut self, args: impl IntoIterator<Item = String>) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    /// Sets the character set exiftool uses for metadata values, passed as `-charset` on
+    /// every call. Without this, values which aren't valid in exiftool's default (UTF-8)
+    /// are passed through as-is, and are replaced with U+FFFD wherever this crate needs text.
+    pub fn charset(mut self, charset: &str) -> Self {
+        self.charset = Some(charset.to_string());
+        self
+    }
+
+    /// Sets the character set of file names, passed as `-charset filename=CHARSET` on every
+    /// call. This is separate from [`ExifToolBuilder::charset`], which is for metadata
+    /// values. On Windows, exiftool can't open files whose names use characters outside the
+    /// system code page, such as CJK names on a Western system, unless this is set, usually
+    /// to `utf8`.
+    pub fn filename_charset(mut self, charset: &str) -> Self {
+        self.filename_charset = Some(charset.to_string());
+        self
+    }
+
+    /// Sets how tag names are qualified with their group on every call. JSON keys then
+    /// carry the group prefix, so typed structs need matching `#[serde(rename)]`s.
+    #[cfg_attr(
+        feature = "async",
+        doc = "The typed helpers, such as [`ExifTool::summarize`], find their tags whatever the group."
+    )]
+    pub fn group_mode(mut self, group_mode: GroupMode) -> Self {
+        self.group_mode = group_mode;
+        self
+    }
+
+    /// Sets how much of each file is scanned on every call. Faster scans save a lot of I/O
+    /// on large RAW and video files when only header metadata is needed.
+    pub fn scan_speed(mut self, scan_speed: ScanSpeed) -> Self {
+        self.scan_speed = scan_speed;
+        self
+    }
+
+    /// If set, passes `-n` on every call, so that values are printed as raw numbers (`0.005`)
+    /// rather than converted for display (`1/200`). This lets typed structs deserialize them
+    /// into numeric fields. A single tag can be read numerically by suffixing its name with
+    /// `#`, as in `ExposureTime#`.
+    pub fn numeric(mut self, numeric: bool) -> Self {
+        self.numeric = numeric;
+        self
+    }
+
+    /// If set, passes `-ee` on every call, so that metadata is also read from embedded
+    /// documents, such as the pages of a multi-page TIFF or the timed metadata of a video.
+    /// For long videos this can produce a very large amount of output, all of which is
+    /// buffered in memory.
+    pub fn extract_embedded(mut self, extract_embedded: bool) -> Self {
+        self.extract_embedded = extract_embedded;
+        self
+    }
+
+    /// If set, passes `-struct` on every call, so that structured XMP tags, such as the
+    /// face regions of `RegionInfo`, are returned as nested JSON objects and arrays rather
+    /// than flattened into a tag per field.
+    pub fn structured(mut self, structured: bool) -> Self {
+        self.structured = structured;
+        self
+    }
+
+    /// If set, passes `-a` on every call, so that tags which appear in several groups, such
+    /// as a `Comment` in both the EXIF and the XMP, are all returned rather than only the
+    /// one exiftool prefers.
+    ///
+    /// JSON keys would then collide, leaving only the last of each, so this implies
+    /// [`GroupMode::Group1`] unless another [`ExifToolBuilder::group_mode`] is set. Tags
+    /// which repeat within a single group can still share a key.
+    #[cfg_attr(
+        feature = "async",
+        doc = "[`ExifTool::execute_json_ordered`] keeps these apart."
+    )]
+    #[cfg_attr(
+        feature = "async",
+        doc = "The typed helpers, such as [`ExifTool::summarize`], take the first group of each tag they read."
+    )]
+    pub fn allow_duplicates(mut self, allow_duplicates: bool) -> Self {
+        self.allow_duplicates = allow_duplicates;
+        self
+    }
+
+    /// If set, passes `-m` on every call, so that exiftool carries on past minor errors
+    /// instead of failing with [`ExifToolError::MinorError`]. They are reported as
+    /// [`ExifToolOutput::warnings`] instead.
+    pub fn ignore_minor_errors(mut self, ignore_minor_errors: bool) -> Self {
+        self.ignore_minor_errors = ignore_minor_errors;
+        self
+    }
+
+    /// Sets an exiftool API option, passed as `-api KEY=VALUE` on every call. Setting the
+    /// same option again replaces its value. Commonly used options include:
+    ///
+    /// - `QuickTimeUTC=1`, which treats QuickTime timestamps as UTC, as the specification
+    ///   says they are, and converts them to local time. Without it, video timestamps are
+    ///   printed as stored, which is usually UTC but looks like local time.
+    /// - `LargeFileSupport=1`, which allows reading files over 2 GB.
+    /// - `RequestAll=3`, which also extracts tags exiftool normally only returns when they
+    ///   are asked for by name, such as `FileAccessDate`.
+    /// - `Filter=...`, a Perl expression applied to every value that is printed.
+    ///
+    /// Spawning fails with [`ExifToolError::InvalidArgument`] if `key` isn't a valid option
+    /// name.
+    pub fn api_option(mut self, key: &str, value: impl ToString) -> Self {
+        let value = value.to_string();
+        match self
+            .api_options
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        {
+            Some((_, v)) => *v = value,
+            None => self.api_options.push((key.to_string(), value)),
+        }
+        self
+    }
+
+    /// Arguments which apply to every call, in a fixed order: the charsets, so that
+    /// everything after is read with them, then the output options, then `-api` options, and
+    /// last the args from [`ExifToolBuilder::arg`], which can so override any of the rest.
+    fn common_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(charset) = &self.charset {
+            args.extend(["-charset".to_string(), charset.clone()]);
+        }
+        // `-charset` takes a single value, so the two are passed separately.
+        if let Some(charset) = &self.filename_charset {
+            args.extend(["-charset".to_string(), format!("filename={charset}")]);
+        }
+        let group_mode = match self.group_mode {
+            GroupMode::None if self.allow_duplicates => GroupMode::Group1,
+            group_mode => group_mode,
+        };
+        args.extend(group_mode.arg().map(str::to_string));
+        args.extend(self.scan_speed.arg().map(str::to_string));
+        if self.numeric {
+            args.push("-n".to_string());
+        }
+        if self.extract_embedded {
+            args.push("-ee".to_string());
+        }
+        if self.structured {
+            args.push("-struct".to_string());
+        }
+        if self.allow_duplicates {
+            args.push("-a".to_string());
+        }
+        if self.ignore_minor_errors {
+            args.push("-m".to_string());
         }
+        for (key, value) in &self.api_options {
+            args.extend(["-api".to_string(), format!("{key}={value}")]);
+        }
+        args.extend(self.args.iter().cloned());
+        args
+    }
+
+    /// The arguments of a call with `params`, as they would be given to a standalone
+    /// exiftool, without the framing of the `-stay_open` protocol.
+    fn command_line(&self, params: Vec<String>) -> Vec<String> {
+        self.common_args().into_iter().chain(params).collect()
     }
 
-    pub async fn execute(&self, params: Vec<String>) -> ExifToolOutput {
-        let signal_num = 193280; // TODO: random #
+    /// Assembles the message to send exiftool for `params`, along with the markers which
+    /// will delimit its response. `signal_num` must differ from that of
+    /// the previous command, so that its markers can't be mistaken for this one's.
+    ///
+    /// Arguments are sent one per line, so any containing a line break are rejected rather
+    /// than being split into several arguments.
+    fn command(
+        &self,
+        params: Vec<String>,
+        signal_num: u64,
+        with_status: bool,
+    ) -> Result<(Vec<u8>, Markers), ExifToolError> {
+        if let Some(arg) = params.iter().find(|arg| arg.contains(['\n', '\r'])) {
+            return Err(ExifToolError::InvalidArgument(format!(
+                "{arg:?} contains a line break"
+            )));
+        }
+        log::debug!(
+            "exiftool {}",
+            shell_words(&self.command_line(params.clone()))
+        );
 
         // # constant special sequences when running -stay_open mode
         let seq_execute = format!("-execute{}", signal_num); // the default string is b"-execute\n"
-        let seq_ready = format!("{{ready{}}}", signal_num); // the default string is b"{ready}"
-        let seq_err_post = format!("post{}", signal_num); //default there isn't any string
+        let markers = Markers {
+            ready: format!("{{ready{}}}", signal_num), // the default string is b"{ready}"
+            err_post: format!("post{}", signal_num),   //default there isn't any string
+            with_status,
+        };
 
         let seq_err_status = "${status}"; // a special sequence, ${status} returns EXIT STATUS as per exiftool documentation - only supported on exiftool v12.10+
 
         let mut cmd_params: Vec<_> = params.into_iter().map(|s| s.into_bytes()).collect();
         cmd_params.push(b"-echo4".to_vec());
-        cmd_params.push(
-            format!("{SEQ_ERR_STATUS_DELIM}{seq_err_status}{SEQ_ERR_STATUS_DELIM}{seq_err_post}")
+        if with_status {
+            cmd_params.push(
+                format!(
+                    "{SEQ_ERR_STATUS_DELIM}{seq_err_status}{SEQ_ERR_STATUS_DELIM}{}",
+                    markers.err_post
+                )
                 .into_bytes(),
-        );
+            );
+        } else {
+            cmd_params.push(markers.err_post.clone().into_bytes());
+        }
         cmd_params.push(seq_execute.into_bytes());
         let message = {
             let mut s = Vec::new();
@@ -99,78 +1252,2625 @@ impl ExifTool {
             }
             s
         };
+        Ok((message, markers))
+    }
+
+    /// If set, a call which finds that the exiftool process has exited respawns it instead
+    /// of failing with [`ExifToolError::Exited`].
+    pub fn auto_restart(mut self, auto_restart: bool) -> Self {
+        self.auto_restart = auto_restart;
+        self
+    }
 
-        let (mut raw_stdout, mut raw_stderr) = {
-            let mut process = self.process.lock().await;
-            let stdin = process.stdin.as_mut().unwrap();
-            stdin.write_all(&message).unwrap();
-            stdin.flush().unwrap();
+    /// Retries reads which fail with [`ExifToolError::Io`], [`ExifToolError::Timeout`] or
+    /// [`ExifToolError::ProcessGone`], such as from slow network storage or an exiftool
+    /// which crashed, up to `max_attempts` attempts in all. The first retry waits `backoff`,
+    /// and each later one twice as long as the last. The failed attempt leaves the process
+    /// poisoned or gone, so it is [`ExifTool::reset`] before each retry. Reads only time out
+    /// if [`ExifToolBuilder::read_timeout`] is set.
+    ///
+    /// Only the JSON reads, [`ExifTool::execute_json`] and [`ExifTool::execute_struct`],
+    /// and the methods built on them, along with [`ExifTool::extract_binary`], are
+    /// retried. Errors which exiftool reported would only recur, and writes, or anything
+    /// run with [`ExifTool::execute`], may have taken effect before failing, so they are
+    /// never retried.
+    #[cfg(feature = "async")]
+    pub fn retry(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.retry = Some((max_attempts, backoff));
+        self
+    }
 
-            let stdout = process.stdout.as_mut().unwrap();
-            let raw_stdout = read_fd_ends_with(stdout, seq_ready.as_str(), 4096).await;
+    /// Makes the reads which [`ExifToolBuilder::retry`] applies to fail with
+    /// [`ExifToolError::Timeout`] if exiftool takes longer than `timeout` to answer, as
+    /// [`ExifTool::execute_with_timeout`] does. With retries set, they are then retried.
+    #[cfg(feature = "async")]
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
 
-            let stderr = process.stderr.as_mut().unwrap();
-            let raw_stderr = read_fd_ends_with(stderr, seq_err_post.as_str(), 4096).await;
+    /// Sets how many bytes are read from exiftool's output at a time, which defaults to
+    /// 64 KiB. Larger buffers mean fewer reads for large binary extractions. When streaming
+    /// binary output to a writer, this is also roughly the size of each write.
+    pub fn read_buffer_size(mut self, read_buffer_size: usize) -> Self {
+        self.read_buffer_size = Some(read_buffer_size);
+        self
+    }
 
-            (raw_stdout, raw_stderr)
-        };
+    fn block_size(&self) -> usize {
+        self.read_buffer_size.unwrap_or(DEFAULT_READ_BUFFER_SIZE).max(1)
+    }
 
-        trim_end(&mut raw_stdout);
-        trim_end(&mut raw_stderr);
-        raw_stdout.truncate(raw_stdout.len() - seq_ready.len());
-        raw_stderr.truncate(raw_stderr.len() - seq_err_post.len());
+    /// The separator set with a `-sep` argument, which exiftool joins list items with.
+    #[cfg(feature = "async")]
+    fn list_separator(&self) -> Option<&str> {
+        self.args
+            .iter()
+            .position(|arg| arg.eq_ignore_ascii_case("-sep"))
+            .and_then(|pos| self.args.get(pos + 1))
+            .map(String::as_str)
+    }
 
-        let err_status_delim = SEQ_ERR_STATUS_DELIM;
-        if !raw_stderr.ends_with(err_status_delim.as_bytes()) {
-            panic!("exiftool stderr did not end with {err_status_delim}");
-        }
+    /// Makes spawning fail with [`ExifToolError::UnsupportedVersion`] if exiftool is older
+    /// than `(major, minor)`, such as `(12, 10)` for `${status}` support.
+    pub fn min_version(mut self, min_version: (u32, u32)) -> Self {
+        self.min_version = Some(min_version);
+        self
+    }
 
-        let status_code = {
-            let delim_len = err_status_delim.len();
-            let next_delim = raw_stderr[..raw_stderr.len() - delim_len]
-                .rfind(err_status_delim)
-                .unwrap();
-            let status_code = &raw_stderr[next_delim + delim_len..raw_stderr.len() - delim_len];
-            let status_code = u8::from_str(std::str::from_utf8(status_code).unwrap()).unwrap();
-            raw_stderr.truncate(next_delim);
-            status_code
-        };
+    /// Spawns exiftool. If a minimum version was set, this first runs `exiftool -ver` to
+    /// check it, blocking until that finishes.
+    #[cfg(feature = "async")]
+    pub fn build(self) -> Result<ExifTool, ExifToolError> {
+        let config = self.resolve()?;
+        Ok(ExifTool {
+            process: ManuallyDrop::new(Mutex::new(config.spawn()?)),
+            config,
+            poisoned: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            signal_num: AtomicU64::new(initial_signal_num()),
+            capabilities: std::sync::Mutex::new(None),
+            last_command: std::sync::Mutex::new(Vec::new()),
+            extensions: std::sync::Mutex::new(HashMap::new()),
+        })
+    }
 
-        ExifToolOutput {
-            status: status_code,
-            output: raw_stdout,
-            error: raw_stderr,
+    /// Fills in the default binary and checks the API options and minimum version, before
+    /// spawning.
+    fn resolve(mut self) -> Result<Self, ExifToolError> {
+        if self.binary.is_none() {
+            self.binary = Some(
+                std::env::var_os("EXIFTOOL")
+                    .unwrap_or("exiftool".into())
+                    .into(),
+            );
         }
+        // exiftool's option names are alphanumeric, and anything else would be parsed as
+        // part of the value, or as a different option.
+        if let Some((key, _)) = self
+            .api_options
+            .iter()
+            .find(|(key, _)| key.is_empty() || !key.bytes().all(|b| b.is_ascii_alphanumeric()))
+        {
+            return Err(ExifToolError::InvalidArgument(format!(
+                "{key:?} is not an exiftool API option"
+            )));
+        }
+        if let Some(required) = self.min_version {
+            let path = self.binary_path();
+            let output = self
+                .configure(std::process::Command::new(path))
+                .arg("-ver")
+                .output()
+                .map_err(|source| ExifToolError::SpawnFailed {
+                    path: path.display().to_string(),
+                    source,
+                })?;
+            let found =
+                parse_version(&output.stdout).ok_or_else(|| ExifToolError::InvalidValue {
+                    tag: "version".to_string(),
+                    value: String::from_utf8_lossy(&output.stdout).into_owned(),
+                })?;
+            if found < required {
+                return Err(ExifToolError::UnsupportedVersion { found, required });
+            }
+        }
+        Ok(self)
     }
 
-    pub async fn execute_json(&self, mut params: Vec<String>) -> Value {
-        params.insert(0, "-j".to_string());
-        serde_json::from_slice(&self.execute(params).await.output).unwrap()
+    fn binary_path(&self) -> &Path {
+        self.binary.as_deref().unwrap_or("exiftool".as_ref())
     }
 
+    /// Applies the configured working directory and environment to `command`.
+    fn configure(&self, mut command: std::process::Command) -> std::process::Command {
+        if let Some(current_dir) = &self.current_dir {
+            command.current_dir(current_dir);
+        }
+        if self.env_clear {
+            command.env_clear();
+        }
+        command.envs(self.env.iter().map(|(key, value)| (key, value)));
+        command
+    }
+
+    /// An exiftool command line with piped stdio, run as `launch` says.
+    fn std_command(&self, launch: Launch) -> std::process::Command {
+        let mut command = self.configure(std::process::Command::new(self.binary_path()));
+        command
+            .args(self.launch_args(launch))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    fn spawn_failed(&self, source: io::Error) -> ExifToolError {
+        ExifToolError::SpawnFailed {
+            path: self.binary_path().display().to_string(),
+            source,
+        }
+    }
+
+    /// The full command line of an exiftool process run as `launch` says. Every process is
+    /// started through this, so that its arguments always come in the order exiftool needs:
+    ///
+    /// 1. `-config`, which exiftool only honours as the very first argument.
+    /// 2. For a `-stay_open` process, `-stay_open True -@ -`, then `-common_args` followed
+    ///    by the common args. Everything after `-common_args` is applied to every command
+    ///    read from stdin, so nothing else may follow them. Passing them once here saves
+    ///    sending and parsing them again on every call.
+    /// 3. For a single run, the common args, then its own params, so that these can
+    ///    override the configured ones.
+    fn launch_args(&self, launch: Launch) -> Vec<OsString> {
+        let mut args = Vec::new();
+        if let Some(config_file) = &self.config_file {
+            args.extend([OsString::from("-config"), config_file.into()]);
+        }
+        let common_args = self.common_args();
+        match launch {
+            Launch::StayOpen => {
+                args.extend(["-stay_open", "True", "-@", "-"].map(OsString::from));
+                if !common_args.is_empty() {
+                    args.push("-common_args".into());
+                    args.extend(common_args.into_iter().map(OsString::from));
+                }
+            }
+            #[cfg(feature = "async")]
+            Launch::Once(params) => {
+                args.extend(common_args.into_iter().chain(params).map(OsString::from));
+            }
+        }
+        args
+    }
+
+    /// Spawns exiftool with piped stdio, run as `launch` says.
+    #[cfg(feature = "async")]
+    fn spawn_with_args(&self, launch: Launch) -> Result<Child, ExifToolError> {
+        tokio::process::Command::from(self.std_command(launch))
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|source| self.spawn_failed(source))
+    }
+
+    #[cfg(feature = "async")]
+    fn spawn(&self) -> Result<Child, ExifToolError> {
+        self.spawn_with_args(Launch::StayOpen)
+    }
+}
+
+/// How an exiftool process is run, which decides how [`ExifToolBuilder::launch_args`]
+/// lays out its command line.
+enum Launch {
+    /// A `-stay_open` process, which reads its commands from stdin.
+    StayOpen,
+    /// A single run with the given params.
+    #[cfg(feature = "async")]
+    Once(Vec<String>),
+}
+
+/// How many files a write changed, as counted in exiftool's summary. Files created with
+/// `-o` count as updated.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct WriteSummary {
+    pub updated: u32,
+    pub unchanged: u32,
+    /// Files which weren't written because of an error.
+    pub errors: u32,
+    /// For writes to several files, the files which failed, along with the error exiftool
+    /// reported for each. Writes to a single file fail outright instead.
+    pub failures: Vec<(PathBuf, String)>,
+    /// The warnings exiftool printed, such as for tags which an expression referred to but
+    /// the source file doesn't have.
+    pub warnings: Vec<String>,
+}
+
+impl WriteSummary {
+    /// Sums up the `N image files updated` style lines of exiftool's output. Counts which
+    /// exiftool didn't print are zero.
+    fn parse(output: &[u8]) -> Self {
+        let mut summary = WriteSummary::default();
+        for line in output.lines() {
+            let line = match line.trim().to_str() {
+                Ok(line) => line,
+                Err(_) => continue,
+            };
+            let (count, rest) = match line.split_once(' ') {
+                Some((count, rest)) => match count.parse::<u32>() {
+                    Ok(count) => (count, rest),
+                    Err(_) => continue,
+                },
+                None => continue,
+            };
+            let counter = match rest {
+                "image files updated" | "image files created" | "output files created" => {
+                    &mut summary.updated
+                }
+                "image files unchanged" => &mut summary.unchanged,
+                "files weren't updated due to errors" | "files weren't created due to errors" => {
+                    &mut summary.errors
+                }
+                _ => continue,
+            };
+            *counter += count;
+        }
+        summary
+    }
+}
+
+pub struct ExifToolOutput {
+    /// The status exiftool reported for the call: 0 for success, 1 for an error, and 2 if
+    /// every file failed an `-if` condition.
+    pub status: i32,
+    pub output: Vec<u8>,
+    pub error: Vec<u8>,
+    /// The messages of the `Warning:` lines in `error`. exiftool can print these even when
+    /// it succeeds, for problems such as a minor corrupt metadata block.
+    pub warnings: Vec<String>,
+}
+
+impl ExifToolOutput {
+    fn new(status: i32, output: Vec<u8>, error: Vec<u8>) -> Self {
+        let warnings = error
+            .lines()
+            .filter_map(|line| line.strip_prefix(b"Warning:"))
+            .map(|warning| String::from_utf8_lossy(warning.trim()).into_owned())
+            .collect();
+        ExifToolOutput {
+            status,
+            output,
+            error,
+            warnings,
+        }
+    }
+
+    /// Whether exiftool exited with status 0. Writes which left a file unchanged, and
+    /// diagnostics which are only warnings, still count as success, while status 2, for
+    /// every file failing an `-if` condition, doesn't.
+    pub fn is_success(&self) -> bool {
+        self.status == 0
+    }
+
+    /// stdout as text, with invalid UTF-8 replaced by U+FFFD.
+    pub fn stdout_str(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.output)
+    }
+
+    /// stderr as text, with invalid UTF-8 replaced by U+FFFD.
+    pub fn stderr_str(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.error)
+    }
+
+    /// Converts the diagnostics exiftool printed into an error, preferring any `Error:`
+    /// lines on stderr over the rest of stderr, then stdout. If every error is marked
+    /// `[minor]`, this is an [`ExifToolError::MinorError`], and otherwise the fatal
+    /// [`ExifToolError::Reported`], or [`ExifToolError::FileNotFound`] if every error is a
+    /// missing file.
+    fn reported_error(&self) -> ExifToolError {
+        let errors: Vec<&[u8]> = self
+            .error
+            .lines()
+            .filter(|line| line.starts_with(b"Error"))
+            .collect();
+        if !errors.is_empty() && errors.iter().all(|line| line.contains_str("[minor]")) {
+            let message = errors.join(&b'\n');
+            return ExifToolError::MinorError(String::from_utf8_lossy(&message).into_owned());
+        }
+        // If any file was found, its own error is more useful than that others were missing.
+        let missing: Vec<&[u8]> = errors
+            .iter()
+            .filter_map(|line| line.strip_prefix(b"Error: File not found - "))
+            .collect();
+        if let Some(path) = missing.first().filter(|_| missing.len() == errors.len()) {
+            return ExifToolError::FileNotFound(PathBuf::from(
+                String::from_utf8_lossy(path.trim_end()).into_owned(),
+            ));
+        }
+        let message = if !errors.is_empty() {
+            errors.join(&b'\n')
+        } else {
+            match self.error.trim() {
+                [] => self.output.trim(),
+                error => error,
+            }
+            .to_vec()
+        };
+        ExifToolError::Reported(String::from_utf8_lossy(&message).into_owned())
+    }
+
+    /// Checks that a write to a single file succeeded, whether it changed the file or found
+    /// it already as asked. Warnings, such as for tags which the file format can't hold,
+    /// don't count as failures.
+    #[cfg(feature = "async")]
+    fn check_updated(&self) -> Result<WriteSummary, ExifToolError> {
+        let summary = self.write_summary();
+        if self.status != 0 || summary.updated + summary.unchanged != 1 {
+            return Err(self.reported_error());
+        }
+        Ok(summary)
+    }
+
+    /// Checks that a write with `-o` created its single output file.
+    #[cfg(feature = "async")]
+    fn check_created(&self) -> Result<WriteSummary, ExifToolError> {
+        if self.status != 0 || self.output.find("1 image files created").is_none() {
+            return Err(self.reported_error());
+        }
+        Ok(self.write_summary())
+    }
+
+    /// Counts the files a write updated, left unchanged or failed on, from the summary
+    /// exiftool prints after writing.
+    pub fn write_summary(&self) -> WriteSummary {
+        // The summary goes to stdout, but is counted from stderr too in case exiftool was
+        // told to print it there.
+        let (out, err) = (
+            WriteSummary::parse(&self.output),
+            WriteSummary::parse(&self.error),
+        );
+        WriteSummary {
+            updated: out.updated + err.updated,
+            unchanged: out.unchanged + err.unchanged,
+            errors: out.errors + err.errors,
+            failures: Vec::new(),
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    /// The errors exiftool reported for any of `files`, with the file each names.
+    #[cfg(feature = "async")]
+    fn file_failures(&self, files: &[&str]) -> Vec<(PathBuf, String)> {
+        let mut failures = Vec::new();
+        // exiftool names the file at the end of each error, as in `Error: ... - a.jpg`. The
+        // longest match is taken, in case one file name ends with another.
+        for line in self.error.lines() {
+            let line = String::from_utf8_lossy(line);
+            let message = match line.trim_end().strip_prefix("Error: ") {
+                Some(message) => message,
+                None => continue,
+            };
+            let file = files
+                .iter()
+                .filter(|file| message.ends_with(&format!(" - {file}")))
+                .max_by_key(|file| file.len());
+            if let Some(file) = file {
+                let message = &message[..message.len() - file.len() - 3];
+                failures.push((PathBuf::from(file), message.to_string()));
+            }
+        }
+        failures
+    }
+
+    /// The error for a binary extraction of `tag` which produced no output.
+    fn missing_tag_error(&self, tag: &str) -> ExifToolError {
+        if self.error.trim().is_empty() {
+            ExifToolError::MissingTag(tag.to_string())
+        } else {
+            self.reported_error()
+        }
+    }
+
+    /// Parses stdout as JSON. If exiftool printed nothing but diagnostics, such as when
+    /// the file is missing, those are returned as the error instead.
+    fn parse_json<T: DeserializeOwned>(self) -> Result<T, ExifToolError> {
+        if self.output.trim().is_empty() && !self.error.trim().is_empty() {
+            return Err(self.reported_error());
+        }
+        serde_json::from_slice(&self.output).map_err(|source| ExifToolError::InvalidJson {
+            source,
+            stdout: self.output,
+            stderr: self.error,
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+impl ExifTool {
+    /// Spawns exiftool, as found in the `EXIFTOOL` environment variable or on `PATH`.
+    ///
+    /// # Panics
+    /// Panics if exiftool could not be spawned. Use [`ExifTool::try_new`] to handle this case.
+    pub fn new() -> Self {
+        Self::try_new().unwrap()
+    }
+
+    pub fn try_new() -> Result<Self, ExifToolError> {
+        ExifToolBuilder::new().build()
+    }
+
+    pub fn builder() -> ExifToolBuilder {
+        ExifToolBuilder::new()
+    }
+
+    /// Wraps this `ExifTool` for sharing between tasks. All clones of the `Arc` talk to the
+    /// same exiftool process, one call at a time; use an [`ExifToolPool`] for parallelism.
+    ///
+    /// ```no_run
+    /// # async fn example() -> Result<(), exiftool::ExifToolError> {
+    /// let exiftool = exiftool::ExifTool::try_new()?.shared();
+    /// let tasks: Vec<_> = ["a.jpg", "b.jpg"]
+    ///     .into_iter()
+    ///     .map(|file| {
+    ///         let exiftool = exiftool.clone();
+    ///         tokio::spawn(async move { exiftool.execute(vec![file.to_string()]).await })
+    ///     })
+    ///     .collect();
+    /// for task in tasks {
+    ///     let output = task.await.unwrap()?;
+    ///     println!("{}", String::from_utf8_lossy(&output.output));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn shared(self) -> Arc<ExifTool> {
+        Arc::new(self)
+    }
+
+    pub async fn execute(&self, params: Vec<String>) -> Result<ExifToolOutput, ExifToolError> {
+        self.execute_inner(params, None).await
+    }
+
+    /// Low-level: runs exiftool and returns stdout and stderr exactly as they were read,
+    /// for debugging exiftool's behaviour. Reading stops at this call's markers, which are
+    /// left at the end of each buffer along with the `=${status}=` block on stderr and any
+    /// trailing whitespace.
+    pub async fn execute_raw(
+        &self,
+        params: Vec<String>,
+    ) -> Result<(Vec<u8>, Vec<u8>), ExifToolError> {
+        let (message, markers) = self.command(params, self.status_supported().await?)?;
+        let mut process = self.lock_process().await?;
+        let response =
+            communicate(&mut process, &message, &markers, self.config.block_size()).await?;
+        self.end_response();
+        Ok(response)
+    }
+
+    /// Like [`ExifTool::execute`], but fails with [`ExifToolError::Timeout`] if exiftool has
+    /// not finished responding within `timeout`. This covers the whole call, including
+    /// waiting for other calls to finish with the process.
+    ///
+    /// A response that timed out is still being written by exiftool, so the process is then
+    /// poisoned: every later call returns [`ExifToolError::Poisoned`] rather than reading
+    /// stale output, until [`ExifTool::drain`] or [`ExifTool::reset`] is called. A call which
+    /// times out while still waiting for the process leaves it as it was.
+    pub async fn execute_with_timeout(
+        &self,
+        params: Vec<String>,
+        timeout: Duration,
+    ) -> Result<ExifToolOutput, ExifToolError> {
+        self.execute_inner(params, Some(timeout)).await
+    }
+
+    fn command(
+        &self,
+        params: Vec<String>,
+        with_status: bool,
+    ) -> Result<(Vec<u8>, Markers), ExifToolError> {
+        *self.last_command.lock().unwrap() = self.config.command_line(params.clone());
+        let signal_num = self.signal_num.fetch_add(1, Ordering::Relaxed);
+        self.config.command(params, signal_num, with_status)
+    }
+
+    /// The arguments of the most recent call, including those set on the builder, such as
+    /// `["-n", "-j", "a.jpg"]`. Running exiftool with them reproduces the call, which helps
+    /// with debugging. The `-stay_open` protocol's own arguments are left out. With
+    /// concurrent calls, this is whichever was sent last.
+    ///
+    /// Every command is also logged at debug level with the `log` crate.
+    pub fn last_command(&self) -> Vec<String> {
+        self.last_command.lock().unwrap().clone()
+    }
+
+    /// What the running exiftool supports, which is detected with a `-ver` round trip the
+    /// first time it is needed, and cached until the process is respawned.
+    async fn capabilities(&self) -> Result<Capabilities, ExifToolError> {
+        if let Some(capabilities) = *self.capabilities.lock().unwrap() {
+            return Ok(capabilities);
+        }
+        let (message, markers) = self.command(vec!["-ver".to_string()], false)?;
+        let mut process = self.lock_process().await?;
+        let (raw_stdout, _) =
+            communicate(&mut process, &message, &markers, self.config.block_size()).await?;
+        let version = parse_stdout(raw_stdout, &markers)?;
+        self.end_response();
+        let capabilities = Capabilities::detect(&version);
+        // Still holding the process, so that a respawn can't race with this.
+        *self.capabilities.lock().unwrap() = Some(capabilities);
+        Ok(capabilities)
+    }
+
+    async fn status_supported(&self) -> Result<bool, ExifToolError> {
+        Ok(self.capabilities().await?.status)
+    }
+
+    /// Poisons the process until `end_response` is called, which must happen while the
+    /// process is still locked. A call which fails, panics or is dropped partway through
+    /// reading its response, or can't make sense of it, thereby leaves the process poisoned
+    /// rather than letting the next call read the rest of the response as its own.
+    fn begin_response(&self) {
+        self.poisoned.store(true, Ordering::Release);
+    }
+
+    /// Marks the response begun by `begin_response` as fully read.
+    fn end_response(&self) {
+        self.poisoned.store(false, Ordering::Release);
+    }
+
+    /// Kills the exiftool process and spawns a new one, clearing any poisoning.
+    pub async fn reset(&self) -> Result<(), ExifToolError> {
+        let mut process = self.process.lock().await;
+        let _ = process.kill().await;
+        *process = self.config.spawn()?;
+        self.end_response();
+        self.cancelled.store(false, Ordering::Release);
+        *self.capabilities.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Brings a poisoned process back into sync without respawning it, by reading and
+    /// discarding everything left in its pipes. After a call times out or fails partway
+    /// through its response, every other call fails with [`ExifToolError::Poisoned`] until
+    /// either this or [`ExifTool::reset`] is called.
+    ///
+    /// The end markers of the abandoned response may never be read, so this sends a
+    /// command of its own and reads up to that command's markers instead, which exiftool
+    /// only prints once it has finished everything before it. This waits for a
+    /// long-running command to finish, rather than killing it as `reset` would.
+    pub async fn drain(&self) -> Result<(), ExifToolError> {
+        let mut process = self.process.lock().await;
+        if let Some(status) = process.try_wait()? {
+            return Err(ExifToolError::Exited(status.into()));
+        }
+        self.begin_response();
+        self.read_past_pending(&mut process).await?;
+        // A cancelled call's response comes before this command's, so it was read too.
+        self.cancelled.store(false, Ordering::Release);
+        self.end_response();
+        Ok(())
+    }
+
+    /// Sends a command and reads up to its end markers, discarding everything before them.
+    async fn read_past_pending(&self, process: &mut Child) -> Result<(), ExifToolError> {
+        let signal_num = self.signal_num.fetch_add(1, Ordering::Relaxed);
+        let (message, markers) =
+            self.config
+                .command(vec!["-ver".to_string()], signal_num, false)?;
+        communicate(process, &message, &markers, self.config.block_size()).await?;
+        Ok(())
+    }
+
+    /// Locks the process for a call, respawning it first if it has exited and
+    /// `auto_restart` is set, and begins the call's response. This poisons the process
+    /// before draining a cancelled call's response, so that a call which is dropped
+    /// while draining, such as by a timeout, leaves it poisoned too.
+    async fn lock_process(&self) -> Result<MutexGuard<'_, Child>, ExifToolError> {
+        let mut process = self.process.lock().await;
+        if let Some(status) = process.try_wait()? {
+            if !self.config.auto_restart {
+                return Err(ExifToolError::Exited(status.into()));
+            }
+            *process = self.config.spawn()?;
+            self.poisoned.store(false, Ordering::Release);
+            self.cancelled.store(false, Ordering::Release);
+            *self.capabilities.lock().unwrap() = None;
+        }
+        if self.poisoned.load(Ordering::Acquire) {
+            return Err(ExifToolError::Poisoned);
+        }
+        self.begin_response();
+        let drained = self.drain_cancelled(&mut process).await;
+        reap_if_gone(&mut process, drained).await?;
+        Ok(process)
+    }
+
+    /// Reads and discards the rest of a cancelled call's response.
+    ///
+    /// The cancelled reads may have taken part of its end markers with them, so this reads
+    /// up to the markers of a command of its own instead, as `drain` does.
+    async fn drain_cancelled(&self, process: &mut Child) -> Result<(), ExifToolError> {
+        if self.cancelled.load(Ordering::Acquire) {
+            self.read_past_pending(process).await?;
+            self.cancelled.store(false, Ordering::Release);
+        }
+        Ok(())
+    }
+
+    /// Like [`ExifTool::execute`], but fails with [`ExifToolError::Cancelled`] as soon as
+    /// `token` is cancelled, without killing the process.
+    ///
+    /// exiftool still finishes a cancelled command, so the process stays busy until the
+    /// rest of its response has been read and discarded. This happens at the start of the
+    /// next call, which waits for it.
+    pub async fn execute_with_cancel(
+        &self,
+        params: Vec<String>,
+        token: CancellationToken,
+    ) -> Result<ExifToolOutput, ExifToolError> {
+        if token.is_cancelled() {
+            return Err(ExifToolError::Cancelled);
+        }
+        let (message, markers) = self.command(params, self.status_supported().await?)?;
+        let mut process = self.lock_process().await?;
+        let response = self
+            .read_unless_cancelled(&mut process, &message, &markers, &token)
+            .await;
+        let (raw_stdout, raw_stderr) = match reap_if_gone(&mut process, response).await {
+            Err(ExifToolError::Cancelled) => {
+                // The rest of the response is drained by the next call instead.
+                self.end_response();
+                return Err(ExifToolError::Cancelled);
+            }
+            response => response?,
+        };
+
+        let output = parse_response(raw_stdout, raw_stderr, &markers)?;
+        self.end_response();
+        Ok(output)
+    }
+
+    /// Sends `message` and reads the response like `communicate`, but stops reading once
+    /// `token` is cancelled, noting that the rest of the response is still to be read.
+    async fn read_unless_cancelled(
+        &self,
+        process: &mut Child,
+        message: &[u8],
+        markers: &Markers,
+        token: &CancellationToken,
+    ) -> Result<(Vec<u8>, Vec<u8>), ExifToolError> {
+        // The message is always sent in full, as a partial command can't be drained.
+        send(process, message).await?;
+
+        let block_size = self.config.block_size();
+        let stdout = process.stdout.as_mut().ok_or(ExifToolError::ProcessGone)?;
+        let stderr = process.stderr.as_mut().ok_or(ExifToolError::ProcessGone)?;
+        let read_stdout = read_fd_ends_with(stdout, &markers.ready, block_size);
+        let read_stderr = read_fd_ends_with(stderr, &markers.err_post, block_size);
+        tokio::pin!(read_stdout, read_stderr);
+
+        let (mut raw_stdout, mut raw_stderr) = (None, None);
+        while raw_stdout.is_none() || raw_stderr.is_none() {
+            tokio::select! {
+                output = &mut read_stdout, if raw_stdout.is_none() => raw_stdout = Some(output?),
+                output = &mut read_stderr, if raw_stderr.is_none() => raw_stderr = Some(output?),
+                _ = token.cancelled() => {
+                    self.cancelled.store(true, Ordering::Release);
+                    return Err(ExifToolError::Cancelled);
+                }
+            }
+        }
+        Ok((raw_stdout.unwrap(), raw_stderr.unwrap()))
+    }
+
+    async fn execute_inner(
+        &self,
+        params: Vec<String>,
+        timeout: Option<Duration>,
+    ) -> Result<ExifToolOutput, ExifToolError> {
+        let response = async {
+            let (message, markers) = self.command(params, self.status_supported().await?)?;
+            let mut process = self.lock_process().await?;
+            let (raw_stdout, raw_stderr) =
+                communicate(&mut process, &message, &markers, self.config.block_size()).await?;
+            let output = parse_response(raw_stdout, raw_stderr, &markers)?;
+            self.end_response();
+            Ok(output)
+        };
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, response)
+                .await
+                .map_err(|_| ExifToolError::Timeout)?,
+            None => response.await,
+        }
+    }
+
+    /// Like [`ExifTool::execute`], but for reads which are safe to repeat, so that they are
+    /// retried as configured by [`ExifToolBuilder::retry`].
+    async fn execute_read(&self, params: Vec<String>) -> Result<ExifToolOutput, ExifToolError> {
+        retry_transient(
+            self.config.retry,
+            || self.execute_inner(params.clone(), self.config.read_timeout),
+            || self.reset(),
+        )
+        .await
+    }
+
+    /// Checks that exiftool is still responsive, by waiting up to 2 seconds for it to answer
+    /// `-ver`. This is cheap, as exiftool answers without touching any files.
+    ///
+    /// A long-running service can ping every 30 to 60 seconds or so while idle, and
+    /// [`ExifTool::reset`] the process if it fails. A call which timed out leaves the process
+    /// poisoned, so it needs resetting either way.
+    pub async fn ping(&self) -> Result<(), ExifToolError> {
+        self.execute_with_timeout(vec!["-ver".to_string()], PING_TIMEOUT)
+            .await
+            .map(drop)
+    }
+
+    /// Queries the version of the running exiftool as `(major, minor)`, such as `(12, 40)`.
+    /// This is cached along with the rest of what exiftool supports, so only the first
+    /// call asks exiftool.
+    pub async fn version(&self) -> Result<(u32, u32), ExifToolError> {
+        if let Some(version) = self.capabilities().await?.version {
+            return Ok(version);
+        }
+        let output = self.execute(vec!["-ver".to_string()]).await?.output;
+        parse_version(&output).ok_or(ExifToolError::InvalidValue {
+            tag: "version".to_string(),
+            value: String::from_utf8_lossy(&output).into_owned(),
+        })
+    }
+
+    pub async fn execute_json(&self, mut params: Vec<String>) -> Result<Value, ExifToolError> {
+        params.insert(0, "-j".to_string());
+        self.execute_read(params).await?.parse_json()
+    }
+
+    /// Like [`ExifTool::execute_json`], but includes the values of binary tags, such as
+    /// `ThumbnailImage`, which are otherwise left out. They are base64-encoded in the JSON,
+    /// and can be decoded with [`decode_base64_tag`].
+    pub async fn execute_json_binary(
+        &self,
+        mut params: Vec<String>,
+    ) -> Result<Value, ExifToolError> {
+        params.insert(0, "-b".to_string());
+        self.execute_json(params).await
+    }
+
+    /// Runs exiftool with `-j` and deserializes its output, which is always an array with
+    /// an element per file, into `T`.
+    pub async fn execute_struct<T: DeserializeOwned>(
+        &self,
+        mut params: Vec<String>,
+    ) -> Result<T, ExifToolError> {
+        params.insert(0, "-j".to_string());
+        self.execute_read(params).await?.parse_json()
+    }
+
+    /// Like [`ExifTool::execute_struct`], but for a single file. Fails with
+    /// [`ExifToolError::UnexpectedResultCount`] unless exactly one result is returned.
+    pub async fn execute_struct_one<T: DeserializeOwned>(
+        &self,
+        params: Vec<String>,
+    ) -> Result<T, ExifToolError> {
+        let mut results: Vec<T> = self.execute_struct(params).await?;
+        if results.len() != 1 {
+            return Err(ExifToolError::UnexpectedResultCount(results.len()));
+        }
+        Ok(results.remove(0))
+    }
+
+    /// Runs exiftool with `-j` and returns its JSON as text, for callers which pass it on
+    /// rather than parsing it. The output is checked to be valid JSON. Unless `pretty` is
+    /// set, exiftool's indentation is stripped, which makes large results much smaller.
+    pub async fn execute_json_string(
+        &self,
+        mut params: Vec<String>,
+        pretty: bool,
+    ) -> Result<String, ExifToolError> {
+        params.insert(0, "-j".to_string());
+        let output = self.execute(params).await?;
+        if output.output.trim().is_empty() && !output.error.trim().is_empty() {
+            return Err(output.reported_error());
+        }
+        let json = String::from_utf8(output.output)
+            .map_err(|e| ExifToolError::Io(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        serde_json::from_str::<serde::de::IgnoredAny>(&json)?;
+        Ok(if pretty { json } else { compact_json(&json) })
+    }
+
+    /// Runs exiftool with `-j` on a single file, returning its tags in the order exiftool
+    /// printed them, which follows their order in the file.
+    ///
+    /// Only the top level keeps its order regardless of features. Structured values are
+    /// parsed into [`Value`]s, which are only ordered if this crate's `preserve_order`
+    /// feature, which enables serde_json's, is on.
+    pub async fn execute_json_ordered(
+        &self,
+        params: Vec<String>,
+    ) -> Result<Vec<(String, Value)>, ExifToolError> {
+        let OrderedObject(entries) = self.execute_struct_one(params).await?;
+        Ok(entries)
+    }
+
+    /// Reads `tags` from `files`. To qualify the returned tag names with their groups for
+    /// just this call, pass a [`GroupMode::arg`] in `params`. Tags suffixed with `#` are read
+    /// as numbers, as with [`ExifToolBuilder::numeric`], and are returned without the `#`.
     pub async fn get_tags(
         &self,
         mut params: Vec<String>,
         tags: Vec<String>,
         files: Vec<String>,
-    ) -> Value {
+    ) -> Result<Value, ExifToolError> {
         params.extend(tags.into_iter().map(|mut t| {
             t.insert(0, '-');
             t
         }));
-        params.extend(files.into_iter());
+        params.extend(files);
         self.execute_json(params).await
     }
 
-    pub async fn preview(&self, path: &str) -> Vec<u8> {
-        self
+    /// Reads `tags` from a single file, returning the tag map for just that file. Keys are
+    /// the tag names as exiftool printed them, including any group prefix.
+    pub async fn get_tags_for_file(
+        &self,
+        file: &str,
+        tags: &[&str],
+    ) -> Result<Map<String, Value>, ExifToolError> {
+        let mut params: Vec<String> = tags.iter().map(|tag| format!("-{tag}")).collect();
+        params.push(file.to_string());
+        self.execute_struct_one(params).await
+    }
+
+    /// Reads whichever of `candidates` is present in `file`, preferring earlier ones, such
+    /// as `["LensModel", "LensID", "Lens"]` for a value which makers store under different
+    /// names. All of them are read in a single call. Returns the tag's name as exiftool
+    /// printed it along with its value, or `None` if none is present.
+    pub async fn get_first_available(
+        &self,
+        file: &str,
+        candidates: &[&str],
+    ) -> Result<Option<(String, Value)>, ExifToolError> {
+        let mut tags = self.get_tags_for_file(file, candidates).await?;
+        // exiftool prints tag names in their canonical case, without any group the
+        // candidate named but with any group the builder asked for.
+        let name = |tag: &str| {
+            let tag = tag.trim_end_matches('#');
+            tag.rsplit(':').next().unwrap_or(tag).to_ascii_lowercase()
+        };
+        for candidate in candidates {
+            let candidate = name(candidate);
+            let found = tags
+                .keys()
+                .find(|key| *key != "SourceFile" && name(key) == candidate)
+                .cloned();
+            if let Some(entry) = found.and_then(|key| tags.remove_entry(&key)) {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Checks whether exiftool recognizes the format of `file`. Fails if the file can't be
+    /// read at all, such as when it doesn't exist.
+    pub async fn is_supported(&self, file: &str) -> Result<bool, ExifToolError> {
+        const UNKNOWN: &str = "Unknown file type";
+        let output = self
             .execute(vec![
-                "-b".to_string(),
-                "-PreviewImage".to_string(),
-                path.to_string(),
+                "-j".to_string(),
+                "-FileType".to_string(),
+                "-MIMEType".to_string(),
+                file.to_string(),
             ])
+            .await?;
+        if output.status != 0 && output.error.find(UNKNOWN).is_some() {
+            return Ok(false);
+        }
+
+        let mut results: Vec<Map<String, Value>> = output.parse_json()?;
+        if results.len() != 1 {
+            return Err(ExifToolError::UnexpectedResultCount(results.len()));
+        }
+        let tags = results.remove(0);
+        if find_tag(&tags, "Error")
+            .and_then(Value::as_str)
+            .is_some_and(|error| error.contains(UNKNOWN))
+        {
+            return Ok(false);
+        }
+        Ok(find_tag(&tags, "FileType").is_some() || find_tag(&tags, "MIMEType").is_some())
+    }
+
+    /// Reads a list-type tag, such as `Keywords` or `Subject`, as its items. exiftool prints
+    /// a list with a single item as a plain string, which is returned as that one item, even
+    /// if it contains commas. Only if the builder's arguments include `-sep` are strings
+    /// split, on its separator, as exiftool then joins lists into one. Returns an empty list
+    /// if the tag is absent.
+    pub async fn get_list_tag(&self, file: &str, tag: &str) -> Result<Vec<String>, ExifToolError> {
+        let mut tags = self
+            .execute_struct_one::<Map<String, Value>>(vec![
+                "-struct".to_string(),
+                format!("-{tag}"),
+                file.to_string(),
+            ])
+            .await?;
+        // The key may carry a group prefix, so take whichever tag isn't SourceFile.
+        tags.remove("SourceFile");
+        let items: Vec<String> = match tags.into_iter().next() {
+            Some((_, Value::Array(items))) => items
+                .into_iter()
+                .map(|item| match item {
+                    Value::String(s) => s,
+                    item => item.to_string(),
+                })
+                .collect(),
+            Some((_, Value::String(s))) => match self.config.list_separator() {
+                Some(separator) if !separator.is_empty() => {
+                    s.split(separator).map(str::to_string).collect()
+                }
+                _ => vec![s],
+            },
+            Some((_, Value::Null)) | None => Vec::new(),
+            Some((_, value)) => vec![value.to_string()],
+        };
+        Ok(items
+            .into_iter()
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect())
+    }
+
+    /// Reads the value of a single tag of `file` as exiftool prints it, without the cost of
+    /// JSON. This runs `exiftool -s3`. Returns `None` if the tag is absent.
+    ///
+    /// List tags, such as `Keywords`, come back as one string with their items joined by
+    /// `, `, and exiftool prints line breaks within a value as `.`. If exiftool prints
+    /// several values, such as for a tag found in several groups with `-a`, they are
+    /// returned joined by newlines.
+    pub async fn get_value(&self, file: &str, tag: &str) -> Result<Option<String>, ExifToolError> {
+        let output = self
+            .execute_read(vec!["-s3".to_string(), format!("-{tag}"), file.to_string()])
+            .await?;
+        if output.output.trim().is_empty() {
+            return match output.missing_tag_error(tag) {
+                ExifToolError::MissingTag(_) => Ok(None),
+                e => Err(e),
+            };
+        }
+        Ok(Some(
+            String::from_utf8_lossy(output.output.trim()).into_owned(),
+        ))
+    }
+
+    /// Reads a rational tag of `file`, such as `ExposureTime` or `ExposureCompensation`, as
+    /// `(numerator, denominator)`, without losing precision to a float. Returns `None` if
+    /// the tag is absent.
+    ///
+    /// This reads the value exiftool prints for the tag, which is a fraction for tags such
+    /// as `ExposureTime`, but a possibly rounded decimal for others, or for every tag under
+    /// [`ExifToolBuilder::numeric`]. Fails with [`ExifToolError::InvalidValue`] if it is
+    /// neither.
+    pub async fn get_rational(
+        &self,
+        file: &str,
+        tag: &str,
+    ) -> Result<Option<(i64, i64)>, ExifToolError> {
+        let mut tags = self.get_tags_for_file(file, &[tag]).await?;
+        // The key may carry a group prefix, so take whichever tag isn't SourceFile.
+        tags.remove("SourceFile");
+        let value = match tags.into_iter().next() {
+            Some((_, value)) => value,
+            None => return Ok(None),
+        };
+        match parse_rational(&value) {
+            Some(rational) => Ok(Some(rational)),
+            None => Err(ExifToolError::InvalidValue {
+                tag: tag.to_string(),
+                value: match value {
+                    Value::String(s) => s,
+                    value => value.to_string(),
+                },
+            }),
+        }
+    }
+
+    /// Reads every tag exiftool finds in `file`.
+    pub async fn all_tags(&self, file: &str) -> Result<Map<String, Value>, ExifToolError> {
+        self.execute_struct_one(vec![file.to_string()]).await
+    }
+
+    /// Like [`ExifTool::all_tags`], but with each tag name prefixed by its family 0 group,
+    /// such as `EXIF:Make`.
+    pub async fn all_tags_grouped(&self, file: &str) -> Result<Map<String, Value>, ExifToolError> {
+        self.execute_struct_one(vec!["-G0".to_string(), file.to_string()])
             .await
-            .output
+    }
+
+    /// Reads the metadata of `file` and each document embedded in it, such as the pages of a
+    /// multi-page TIFF. The first map is the main document, followed by the embedded ones
+    /// in order. This always passes `-ee`, with the same memory caveats as
+    /// [`ExifToolBuilder::extract_embedded`].
+    pub async fn embedded_documents(
+        &self,
+        file: &str,
+    ) -> Result<Vec<Map<String, Value>>, ExifToolError> {
+        let tags: Map<String, Value> = self
+            .execute_struct_one(vec!["-ee".to_string(), "-G3".to_string(), file.to_string()])
+            .await?;
+
+        // With -G3, tags are prefixed by their document: "Main", "Doc1", "Doc1-1" and so on.
+        let mut documents: BTreeMap<(u32, String), Map<String, Value>> = BTreeMap::new();
+        for (key, value) in tags {
+            let (document, tag) = match key.split_once(':') {
+                Some((document, tag)) => (document.to_string(), tag.to_string()),
+                None => ("Main".to_string(), key),
+            };
+            let number = document
+                .strip_prefix("Doc")
+                .map(|n| n.split('-').next().unwrap_or(n))
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0);
+            documents
+                .entry((number, document))
+                .or_default()
+                .insert(tag, value);
+        }
+        Ok(documents.into_values().collect())
+    }
+
+    /// Reads the GPS position of `file` in signed decimal degrees, as `(latitude, longitude)`.
+    /// Returns `None` if the file has no GPS data.
+    pub async fn gps_coordinates(&self, file: &str) -> Result<Option<(f64, f64)>, ExifToolError> {
+        let tags = self
+            .get_tags_for_file(
+                file,
+                &[
+                    "GPSLatitude#",
+                    "GPSLongitude#",
+                    "GPSLatitudeRef",
+                    "GPSLongitudeRef",
+                ],
+            )
+            .await?;
+        Ok(gps_from_tags(&tags))
+    }
+
+    /// Writes a GPS position to `file`, in signed decimal degrees, along with an altitude
+    /// in metres if one is given, negative below sea level. The `Ref` tags giving the
+    /// hemisphere are set from the signs, since EXIF coordinates are themselves unsigned.
+    /// The position is then read back to check that it was written, failing with
+    /// [`ExifToolError::Unverified`] if it differs, by which point the file has been written.
+    ///
+    /// Fails with [`ExifToolError::InvalidArgument`] without calling exiftool if `latitude`
+    /// isn't within ±90° or `longitude` within ±180°.
+    pub async fn set_gps(
+        &self,
+        file: &str,
+        latitude: f64,
+        longitude: f64,
+        altitude: Option<f64>,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        if !(-90.0..=90.0).contains(&latitude) {
+            return Err(ExifToolError::InvalidArgument(format!(
+                "latitude {latitude} is not within ±90°"
+            )));
+        }
+        if !(-180.0..=180.0).contains(&longitude) {
+            return Err(ExifToolError::InvalidArgument(format!(
+                "longitude {longitude} is not within ±180°"
+            )));
+        }
+        let mut tags = vec![
+            ("GPSLatitude".to_string(), latitude.abs().to_string()),
+            (
+                "GPSLatitudeRef".to_string(),
+                if latitude < 0.0 { "S" } else { "N" }.to_string(),
+            ),
+            ("GPSLongitude".to_string(), longitude.abs().to_string()),
+            (
+                "GPSLongitudeRef".to_string(),
+                if longitude < 0.0 { "W" } else { "E" }.to_string(),
+            ),
+        ];
+        if let Some(altitude) = altitude {
+            if !altitude.is_finite() {
+                return Err(ExifToolError::InvalidArgument(format!(
+                    "altitude {altitude} is not a number of metres"
+                )));
+            }
+            tags.push(("GPSAltitude".to_string(), altitude.abs().to_string()));
+            // 0 is above sea level, and 1 below.
+            tags.push((
+                "GPSAltitudeRef#".to_string(),
+                u8::from(altitude < 0.0).to_string(),
+            ));
+        }
+        let summary = self.write_tags(file, &tags, backup).await?;
+
+        // EXIF stores each coordinate as rational degrees, minutes and seconds, which rounds
+        // it slightly, so it is compared to within about a metre.
+        let written = self.gps_coordinates(file).await?;
+        let matches = written.is_some_and(|(lat, lon)| {
+            (lat - latitude).abs() < 1e-5 && (lon - longitude).abs() < 1e-5
+        });
+        if !matches {
+            return Err(ExifToolError::Unverified {
+                tag: "GPSPosition".to_string(),
+                value: written.map_or_else(String::new, |(lat, lon)| format!("{lat}, {lon}")),
+            });
+        }
+        Ok(summary)
+    }
+
+    /// Reads the metadata of all `files` with a single exiftool invocation, keyed by the
+    /// requested path. Files which exiftool skipped entirely, such as ones which don't
+    /// exist, are left out, and files for which exiftool reported an `Error` tag map to
+    /// [`ExifToolError::Reported`].
+    pub async fn get_metadata_batch(
+        &self,
+        files: &[impl AsRef<Path>],
+    ) -> Result<HashMap<PathBuf, Result<Value, ExifToolError>>, ExifToolError> {
+        let mut requested: HashMap<String, PathBuf> = files
+            .iter()
+            .map(|file| (source_file(file.as_ref()), file.as_ref().to_path_buf()))
+            .collect();
 
+        let mut params = vec!["-j".to_string()];
+        params.extend(
+            files
+                .iter()
+                .map(|f| f.as_ref().to_string_lossy().into_owned()),
+        );
+        let output = self.execute(params).await?.output;
+        if output.trim().is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let results: Vec<Value> = serde_json::from_slice(&output)?;
+        let mut metadata = HashMap::with_capacity(results.len());
+        for result in results {
+            let path = match result
+                .get("SourceFile")
+                .and_then(Value::as_str)
+                .and_then(|source| requested.remove(source))
+            {
+                Some(path) => path,
+                None => continue,
+            };
+            let error = result.as_object().and_then(|tags| find_tag(tags, "Error"));
+            let result = match error.and_then(Value::as_str) {
+                Some(error) => Err(ExifToolError::Reported(error.to_string())),
+                None => Ok(result),
+            };
+            metadata.insert(path, result);
+        }
+        Ok(metadata)
+    }
+
+    /// Reads the metadata of every file in `dir`, and in its subdirectories if `recursive`
+    /// is set, returning an entry per file. If `extensions` isn't empty, only files with one
+    /// of those extensions, such as `jpg` or `cr2`, are read.
+    ///
+    /// The whole response is buffered before it is parsed, which is fine for a moderate
+    /// number of files, but for thousands it is better to read them in smaller batches.
+    pub async fn get_metadata_dir(
+        &self,
+        dir: &str,
+        recursive: bool,
+        extensions: &[&str],
+    ) -> Result<Vec<Value>, ExifToolError> {
+        self.read_dir(dir, recursive, extensions, Vec::new()).await
+    }
+
+    /// Counts the files in `dir`, and in its subdirectories if `recursive` is set, which
+    /// have one of `extensions`, or any file exiftool can read if it is empty. Only
+    /// `FileName` is read from each, so this is much faster than
+    /// [`ExifTool::get_metadata_dir`]. A faster [`ScanSpeed`] speeds it up further.
+    pub async fn count_files(
+        &self,
+        dir: &str,
+        recursive: bool,
+        extensions: &[&str],
+    ) -> Result<usize, ExifToolError> {
+        let files: Vec<serde::de::IgnoredAny> = self
+            .read_dir(dir, recursive, extensions, vec!["-FileName".to_string()])
+            .await?;
+        Ok(files.len())
+    }
+
+    /// Reads `tags` from those of `paths` for which exiftool's `condition` holds, so that
+    /// files are filtered by exiftool rather than after reading all of them. Directories in
+    /// `paths` are read file by file. An empty `tags` reads every tag.
+    ///
+    /// `condition` is passed as `-if`, and uses exiftool's expression syntax: Perl, with
+    /// tags interpolated by name, as in `$ISO > 1600` or `$Make eq "Canon" and
+    /// $ImageWidth >= 4000`. Files for which it doesn't hold are left out. If none match,
+    /// the result is empty, which exiftool reports with a status of 2 rather than an error.
+    pub async fn get_metadata_if(
+        &self,
+        paths: &[&str],
+        condition: &str,
+        tags: &[&str],
+    ) -> Result<Vec<Value>, ExifToolError> {
+        // -q keeps the `files failed condition` count out of the JSON.
+        let mut params = vec![
+            "-j".to_string(),
+            "-q".to_string(),
+            "-if".to_string(),
+            condition.to_string(),
+        ];
+        params.extend(tags.iter().map(|tag| format!("-{tag}")));
+        params.extend(paths.iter().map(|path| path.to_string()));
+
+        let output = self.execute_read(params).await?;
+        if output.output.trim().is_empty() {
+            let errors = output.error.lines().any(|line| line.starts_with(b"Error"));
+            if !matches!(output.status, 0 | 2) || errors {
+                return Err(output.reported_error());
+            }
+            return Ok(Vec::new());
+        }
+        output.parse_json()
+    }
+
+    /// Reads `tags` from the files in `dir` with the given extensions, deserializing the
+    /// object for each into `T`.
+    async fn read_dir<T: DeserializeOwned>(
+        &self,
+        dir: &str,
+        recursive: bool,
+        extensions: &[&str],
+        tags: Vec<String>,
+    ) -> Result<Vec<T>, ExifToolError> {
+        let mut params = vec!["-j".to_string()];
+        if recursive {
+            params.push("-r".to_string());
+        }
+        for extension in extensions {
+            params.push("-ext".to_string());
+            params.push(extension.trim_start_matches('.').to_string());
+        }
+        params.extend(tags);
+        params.push(dir.to_string());
+
+        let output = self.execute_read(params).await?;
+        // exiftool prints no JSON at all if no files matched.
+        if output.output.trim().is_empty() {
+            if output.status != 0 {
+                return Err(output.reported_error());
+            }
+            return Ok(Vec::new());
+        }
+        output.parse_json()
+    }
+
+    /// Reads `tags` from all `files` with a single exiftool invocation, returning a result
+    /// per requested file, in the order requested. Files which exiftool skipped or reported
+    /// an `Error` for are `None`, so partial failures stay at their index. An empty `tags`
+    /// reads every tag.
+    pub async fn get_tags_ordered(
+        &self,
+        tags: &[&str],
+        files: &[impl AsRef<Path>],
+    ) -> Result<Vec<(PathBuf, Option<Value>)>, ExifToolError> {
+        let mut params = vec!["-j".to_string()];
+        params.extend(tags.iter().map(|tag| format!("-{tag}")));
+        params.extend(
+            files
+                .iter()
+                .map(|f| f.as_ref().to_string_lossy().into_owned()),
+        );
+        let output = self.execute(params).await?.output;
+        let results: Vec<Value> = if output.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_slice(&output)?
+        };
+
+        let by_source: HashMap<String, Value> = results
+            .into_iter()
+            .filter(|result| {
+                let tags = result.as_object();
+                tags.and_then(|tags| find_tag(tags, "Error")).is_none()
+            })
+            .filter_map(|result| {
+                let source = result.get("SourceFile")?.as_str()?.to_string();
+                Some((source, result))
+            })
+            .collect();
+        // Cloned rather than moved out, as a file may have been requested more than once.
+        Ok(files
+            .iter()
+            .map(|file| {
+                let file = file.as_ref();
+                (
+                    file.to_path_buf(),
+                    by_source.get(&source_file(file)).cloned(),
+                )
+            })
+            .collect())
+    }
+
+    /// Writes each `(tag, value)` pair to `file`. An empty value deletes the tag. If `file`
+    /// already holds every value, exiftool leaves it as is, which counts as
+    /// [`WriteSummary::unchanged`] rather than failing.
+    ///
+    /// Like every write method, this takes a [`BackupMode`] which sets what becomes of the
+    /// unmodified file.
+    pub async fn write_tags(
+        &self,
+        file: &str,
+        tags: &[(String, String)],
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        let mut params: Vec<String> = tags
+            .iter()
+            .map(|(tag, value)| format!("-{tag}={value}"))
+            .collect();
+        params.extend(backup.arg().map(str::to_string));
+        params.push(file.to_string());
+
+        self.execute(params).await?.check_updated()
+    }
+
+    /// Like [`ExifTool::write_tags`], but writes the same tags to every file in `files` with
+    /// a single call, such as to set the `Artist` and `Copyright` of a whole shoot.
+    ///
+    /// Files which can't be written don't stop the others. They are counted in
+    /// [`WriteSummary::errors`] and listed with their errors in [`WriteSummary::failures`],
+    /// and only if no file could be written at all does this fail.
+    pub async fn write_tags_batch(
+        &self,
+        files: &[&str],
+        tags: &[(String, String)],
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        if files.is_empty() {
+            return Ok(WriteSummary::default());
+        }
+        let mut params: Vec<String> = tags
+            .iter()
+            .map(|(tag, value)| format!("-{tag}={value}"))
+            .collect();
+        params.extend(backup.arg().map(str::to_string));
+        params.extend(files.iter().map(|file| file.to_string()));
+
+        let output = self.execute(params).await?;
+        let mut summary = output.write_summary();
+        if output.status != 0 && summary.updated + summary.unchanged == 0 {
+            return Err(output.reported_error());
+        }
+        summary.failures = output.file_failures(files);
+        Ok(summary)
+    }
+
+    /// Copies all metadata which `dest` can hold from `source` into `dest`. Tags which
+    /// can't be transferred between the formats are skipped rather than failing the copy.
+    pub async fn copy_metadata(
+        &self,
+        source: &str,
+        dest: &str,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        self.copy_metadata_tags(source, dest, &["all:all"], backup)
+            .await
+    }
+
+    /// Like [`ExifTool::copy_metadata`], but only copies the given tags or groups, such as
+    /// `Artist` or `GPS:all`.
+    pub async fn copy_metadata_tags(
+        &self,
+        source: &str,
+        dest: &str,
+        tags: &[&str],
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        let mut params = vec!["-TagsFromFile".to_string(), source.to_string()];
+        params.extend(tags.iter().map(|tag| format!("-{tag}")));
+        params.extend(backup.arg().map(str::to_string));
+        params.push(dest.to_string());
+        self.execute(params).await?.check_updated()
+    }
+
+    /// Copies tags from `source` into `dest` with exiftool's redirection expressions, which
+    /// can compute new values from the source's tags, such as
+    /// `-Comment<Shot on ${Model} at ${DateTimeOriginal}` or `-XMP:all<EXIF:all`. Each
+    /// expression is passed as is, after `-TagsFromFile source`, which they must follow.
+    ///
+    /// Tags an expression refers to but `source` doesn't have are skipped with a warning,
+    /// which is returned in [`WriteSummary::warnings`]. If that leaves nothing to write,
+    /// this fails.
+    pub async fn copy_with_expressions(
+        &self,
+        source: &str,
+        dest: &str,
+        expressions: &[&str],
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        let mut params = vec!["-TagsFromFile".to_string(), source.to_string()];
+        params.extend(expressions.iter().map(|expression| expression.to_string()));
+        params.extend(backup.arg().map(str::to_string));
+        params.push(dest.to_string());
+        self.execute(params).await?.check_updated()
+    }
+
+    /// Writes `tags` to the XMP sidecar of `image`. If `sidecar` doesn't exist yet, it is
+    /// created as a standalone XMP file holding the metadata of `image` which XMP can
+    /// represent, with `tags` applied on top. Otherwise `tags` are written to the existing
+    /// sidecar in place, and `image` is left untouched either way.
+    pub async fn write_sidecar(
+        &self,
+        image: &str,
+        sidecar: &str,
+        tags: &[(String, String)],
+    ) -> Result<WriteSummary, ExifToolError> {
+        let mut params: Vec<String> = tags
+            .iter()
+            .map(|(tag, value)| format!("-{tag}={value}"))
+            .collect();
+        // exiftool resolves `sidecar` against its own working directory, not ours.
+        let path = match &self.config.current_dir {
+            Some(current_dir) => current_dir.join(sidecar),
+            None => PathBuf::from(sidecar),
+        };
+        if path.exists() {
+            params.push("-overwrite_original".to_string());
+            params.push(sidecar.to_string());
+            return self.execute(params).await?.check_updated();
+        }
+        // exiftool writes the XMP packet wrapper itself when the output ends in .xmp.
+        params.extend(["-o".to_string(), sidecar.to_string(), image.to_string()]);
+        self.execute(params).await?.check_created()
+    }
+
+    /// Has exiftool write what it would have printed for each of `files` into a file of its
+    /// own, with `-w`, such as to keep a `.txt` dump of every image's tags next to it.
+    /// `params` choose the output, as for any call, such as `-j` or `-XMP:all`. Returns how
+    /// many files were written, with the files which couldn't be in
+    /// [`WriteSummary::failures`].
+    ///
+    /// `name_format` names each output file, with exiftool's tokens for the parts of the
+    /// input's path: `%d` for its directory, `%f` for its name without the extension, and
+    /// `%e` for the extension. A format with no `%` is taken as an extension, so
+    /// `txt` is the same as `%d%f.txt`. Existing output files are only replaced if
+    /// `overwrite` is set, and are otherwise failures.
+    pub async fn write_output_files(
+        &self,
+        files: &[&str],
+        mut params: Vec<String>,
+        name_format: &str,
+        overwrite: bool,
+    ) -> Result<WriteSummary, ExifToolError> {
+        if files.is_empty() {
+            return Ok(WriteSummary::default());
+        }
+        let flag = if overwrite { "-w!" } else { "-w" };
+        params.extend([flag.to_string(), name_format.to_string()]);
+        params.extend(files.iter().map(|file| file.to_string()));
+
+        // Nothing is printed but the summary, which counts the output files created.
+        let output = self.execute(params).await?;
+        let mut summary = output.write_summary();
+        if summary.updated == 0 {
+            return Err(output.reported_error());
+        }
+        summary.failures = output.file_failures(files);
+        Ok(summary)
+    }
+
+    /// Reads the XMP tags stored in an XMP sidecar file.
+    pub async fn read_sidecar(&self, sidecar: &str) -> Result<Value, ExifToolError> {
+        self.execute_struct_one(vec!["-XMP:all".to_string(), sidecar.to_string()])
+            .await
+    }
+
+    /// Renames `file` after its metadata, returning its new path. `pattern` is what is copied
+    /// into `FileName`, such as `DateTimeOriginal` or `${Model}_%f.%e`, and `date_format` is
+    /// the `-d` format for any dates in it, such as `%Y-%m-%d_%H-%M.%%e`.
+    ///
+    /// If the new name is taken, this fails with [`ExifToolError::AlreadyExists`], unless the
+    /// pattern includes exiftool's `%-c` counter, in which case exiftool picks a free name.
+    pub async fn rename_by_pattern(
+        &self,
+        file: &str,
+        pattern: &str,
+        date_format: Option<&str>,
+    ) -> Result<PathBuf, ExifToolError> {
+        // -v0 makes exiftool print `'old' --> 'new'` for each file it renames.
+        let mut params = vec!["-v0".to_string()];
+        if let Some(date_format) = date_format {
+            params.push("-d".to_string());
+            params.push(date_format.to_string());
+        }
+        params.push(format!("-FileName<{pattern}"));
+        params.push(file.to_string());
+        let output = self.execute(params).await?;
+
+        let renamed = [&output.output, &output.error]
+            .into_iter()
+            .find_map(|stream| {
+                stream.lines().find_map(|line| {
+                    let (_, new) = line.split_once_str("' --> '")?;
+                    Some(
+                        new.strip_suffix(b"'")
+                            .unwrap_or(new)
+                            .to_str_lossy()
+                            .into_owned(),
+                    )
+                })
+            });
+        if let Some(renamed) = renamed {
+            return Ok(PathBuf::from(renamed));
+        }
+        let exists = output.error.lines().find_map(|line| {
+            let line = line.strip_prefix(b"Error: '")?;
+            let (path, _) = line.split_once_str("' already exists")?;
+            Some(path.to_str_lossy().into_owned())
+        });
+        if let Some(path) = exists {
+            return Err(ExifToolError::AlreadyExists(path));
+        }
+        // The pattern may resolve to the name the file already has.
+        output.check_updated()?;
+        Ok(PathBuf::from(file))
+    }
+
+    /// Sets the GPS position of `file` from a GPX track log, by matching its
+    /// `DateTimeOriginal` against the track's timestamps.
+    ///
+    /// If the photo was taken outside the time range of the track, exiftool only warns and
+    /// leaves the file unchanged, which is returned as an [`ExifToolError::Reported`]
+    /// carrying the warning.
+    pub async fn geotag(
+        &self,
+        file: &str,
+        gpx_path: &str,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        let mut params = vec![
+            "-geotag".to_string(),
+            gpx_path.to_string(),
+            "-Geotime<DateTimeOriginal".to_string(),
+        ];
+        params.extend(backup.arg().map(str::to_string));
+        params.push(file.to_string());
+        let output = self.execute(params).await?;
+        let summary = output.check_updated()?;
+        if summary.updated == 0 {
+            return Err(output.reported_error());
+        }
+        Ok(summary)
+    }
+
+    /// Deletes all metadata from `file`. A file which has no metadata to delete is not an
+    /// error.
+    pub async fn remove_all_metadata(
+        &self,
+        file: &str,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        self.remove_tags(file, &["all"], backup).await
+    }
+
+    /// Deletes the given tags or groups from `file`, such as `Comment` or `gps:all`. Tags
+    /// which are already absent are not an error.
+    pub async fn remove_tags(
+        &self,
+        file: &str,
+        tags: &[&str],
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        let mut params: Vec<String> = tags.iter().map(|tag| format!("-{tag}=")).collect();
+        params.extend(backup.arg().map(str::to_string));
+        params.push(file.to_string());
+        self.execute(params).await?.check_updated()
+    }
+
+    /// Writes the fields of `data` to `file` as tags, using the serialized field names as tag
+    /// names. `None` fields are left untouched, and sequence fields set a list tag to their
+    /// items.
+    pub async fn write_struct<T: Serialize>(
+        &self,
+        file: &str,
+        data: &T,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        let fields = match serde_json::to_value(data)? {
+            Value::Object(fields) => fields,
+            _ => {
+                return Err(ExifToolError::InvalidArgument(
+                    "write_struct needs a struct or map".to_string(),
+                ))
+            }
+        };
+
+        let mut tags = Vec::new();
+        for (tag, value) in fields {
+            match value {
+                Value::Null => {}
+                Value::Array(items) => {
+                    for item in items {
+                        tags.push((tag.clone(), tag_value(&tag, item)?));
+                    }
+                }
+                value => tags.push((tag.clone(), tag_value(&tag, value)?)),
+            }
+        }
+        if tags.is_empty() {
+            return Ok(WriteSummary {
+                unchanged: 1,
+                ..WriteSummary::default()
+            });
+        }
+        self.write_tags(file, &tags, backup).await
+    }
+
+    /// Extracts the binary value of `tag`, such as an embedded image, from `file`. Fails with
+    /// [`ExifToolError::MissingTag`] if the file doesn't have the tag, or
+    /// [`ExifToolError::EmptyBinary`] if it is empty.
+    pub async fn extract_binary(&self, file: &str, tag: &str) -> Result<Vec<u8>, ExifToolError> {
+        let output = self
+            .execute_read(vec!["-b".to_string(), format!("-{tag}"), file.to_string()])
+            .await?;
+        if !output.output.is_empty() {
+            return Ok(output.output);
+        }
+        match output.missing_tag_error(tag) {
+            ExifToolError::MissingTag(_) => {}
+            e => return Err(e),
+        }
+        // -b prints nothing for an absent tag and an empty one alike, so look for it.
+        let mut tags = self.get_tags_for_file(file, &[tag]).await?;
+        tags.remove("SourceFile");
+        Err(if tags.is_empty() {
+            ExifToolError::MissingTag(tag.to_string())
+        } else {
+            ExifToolError::EmptyBinary(tag.to_string())
+        })
+    }
+
+    /// Extracts an embedded image, checking that it isn't a zero-filled placeholder, which
+    /// some cameras write in place of a preview. If it doesn't start like an image, a
+    /// warning is logged, but the bytes are still returned.
+    async fn extract_image(&self, file: &str, tag: &str) -> Result<Vec<u8>, ExifToolError> {
+        let image = self.extract_binary(file, tag).await?;
+        if image.iter().all(|&b| b == 0) {
+            return Err(ExifToolError::EmptyBinary(tag.to_string()));
+        }
+        if !looks_like_image(&image) {
+            log::warn!("the {tag} of {file} is not in a recognized image format");
+        }
+        Ok(image)
+    }
+
+    /// Like [`ExifTool::extract_binary`], but streams the value into `writer` as exiftool
+    /// produces it instead of buffering it in memory. Returns the number of bytes written.
+    ///
+    /// If `writer` fails, the rest of the response is left unread, so the process is
+    /// poisoned.
+    pub async fn extract_binary_to<W: AsyncWrite + Unpin>(
+        &self,
+        file: &str,
+        tag: &str,
+        writer: &mut W,
+    ) -> Result<u64, ExifToolError> {
+        let (message, markers) = self.command(
+            vec!["-b".to_string(), format!("-{tag}"), file.to_string()],
+            self.status_supported().await?,
+        )?;
+        let mut process = self.lock_process().await?;
+        let (written, raw_stderr) = {
+            let process = &mut *process;
+            let block_size = self.config.block_size();
+            let response = async {
+                send(process, &message).await?;
+
+                let stdout = process.stdout.as_mut().ok_or(ExifToolError::ProcessGone)?;
+                let stderr = process.stderr.as_mut().ok_or(ExifToolError::ProcessGone)?;
+                tokio::try_join!(
+                    stream_fd_ends_with(stdout, &markers.ready, block_size, writer),
+                    read_fd_ends_with(stderr, &markers.err_post, block_size),
+                )
+            }
+            .await;
+            reap_if_gone(process, response).await?
+        };
+
+        let (status, error) = parse_stderr(raw_stderr, &markers)?;
+        self.end_response();
+        if written == 0 {
+            let output = ExifToolOutput::new(status, Vec::new(), error);
+            return Err(output.missing_tag_error(tag));
+        }
+        Ok(written)
+    }
+
+    /// Reads the metadata of an in-memory file, which exiftool identifies by its contents.
+    ///
+    /// Since the stdin of the `-stay_open` process is its command channel, this spawns a
+    /// separate exiftool process for every call and pipes `data` into it. That costs a
+    /// full exiftool startup each time, so prefer the path-based methods for files which
+    /// are already on disk.
+    ///
+    /// There is no file name for exiftool to take a type from, so this takes no format
+    /// hint: exiftool goes by the magic number of `data` alone. The few formats which
+    /// exiftool can only recognise by their extension need writing to a file instead.
+    pub async fn metadata_from_bytes(&self, data: &[u8]) -> Result<Value, ExifToolError> {
+        let launch = Launch::Once(vec!["-j".to_string(), "-".to_string()]);
+        let mut process = self.config.spawn_with_args(launch)?;
+
+        let mut stdin = process.stdin.take().ok_or(ExifToolError::ProcessGone)?;
+        let write = async move {
+            // exiftool stops reading once it has found all the metadata it needs.
+            match stdin.write_all(data).await {
+                Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e),
+                _ => Ok(()),
+            }
+        };
+        let (written, output) = tokio::join!(write, process.wait_with_output());
+        written?;
+        let output = output?;
+
+        let status = match ProcessExit::from(output.status) {
+            ProcessExit::Code(code) => code,
+            exit => return Err(ExifToolError::Exited(exit)),
+        };
+        ExifToolOutput::new(status, output.stdout, output.stderr).parse_json()
+    }
+
+    /// Extracts the embedded ICC colour profile of `file`, as the bytes of a standalone
+    /// `.icc` file. Returns `None` if the file has no profile, while failing to read the
+    /// file at all is still an error.
+    pub async fn icc_profile(&self, file: &str) -> Result<Option<Vec<u8>>, ExifToolError> {
+        match self.extract_binary(file, "ICC_Profile").await {
+            Ok(profile) => Ok(Some(profile)),
+            Err(ExifToolError::MissingTag(_) | ExifToolError::EmptyBinary(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The extensions of the file types exiftool can read, in lowercase, such as `jpg` and
+    /// `cr3`. This only asks exiftool the first time, as the list is fixed by its version.
+    pub async fn supported_read_extensions(&self) -> Result<Vec<String>, ExifToolError> {
+        self.list_extensions("-listf").await
+    }
+
+    /// The extensions of the file types exiftool can write metadata to, in lowercase, as
+    /// with [`ExifTool::supported_read_extensions`].
+    pub async fn supported_write_extensions(&self) -> Result<Vec<String>, ExifToolError> {
+        self.list_extensions("-listwf").await
+    }
+
+    /// Runs one of exiftool's file type listings and parses the extensions out of it.
+    async fn list_extensions(&self, option: &'static str) -> Result<Vec<String>, ExifToolError> {
+        if let Some(extensions) = self.extensions.lock().unwrap().get(option) {
+            return Ok(extensions.clone());
+        }
+        let output = self.execute_read(vec![option.to_string()]).await?;
+        if !output.is_success() {
+            return Err(output.reported_error());
+        }
+        // The extensions are listed several to a line, under a heading such as
+        // `Supported file extensions:`.
+        let extensions: Vec<String> = output
+            .stdout_str()
+            .lines()
+            .filter(|line| !line.contains(':'))
+            .flat_map(str::split_whitespace)
+            .map(str::to_ascii_lowercase)
+            .collect();
+        self.extensions
+            .lock()
+            .unwrap()
+            .insert(option, extensions.clone());
+        Ok(extensions)
+    }
+
+    /// Extracts the raw maker notes of `file`, the vendor-specific block in which cameras
+    /// record settings beyond the standard EXIF tags. Returns `None` if the file has none.
+    ///
+    /// Its layout differs by maker and often by model, so this is only useful to code
+    /// which parses it itself. It can be hundreds of kilobytes, all of which is buffered in
+    /// memory; [`ExifTool::extract_binary_to`] with `MakerNotes` streams it instead. Where
+    /// a file has several maker-note blocks, such as a copy in XMP, this is the one
+    /// exiftool treats as primary, in the EXIF block.
+    pub async fn maker_notes(&self, file: &str) -> Result<Option<Vec<u8>>, ExifToolError> {
+        match self.extract_binary(file, "MakerNotes").await {
+            Ok(notes) => Ok(Some(notes)),
+            Err(ExifToolError::MissingTag(_) | ExifToolError::EmptyBinary(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Extracts every embedded image in `file`, such as `PreviewImage`, `ThumbnailImage` and
+    /// `JpgFromRaw`, keyed by tag name. This takes a call to find which exiftool's
+    /// `Preview` group holds, and another for each image. Images which fail to extract
+    /// are left out, rather than failing the rest.
+    pub async fn extract_all_previews(
+        &self,
+        file: &str,
+    ) -> Result<HashMap<String, Vec<u8>>, ExifToolError> {
+        let tags = self.get_tags_for_file(file, &["Preview:all"]).await?;
+        let mut previews = HashMap::new();
+        // Without -b, binary values are printed as a placeholder giving their size.
+        let binary = tags.iter().filter(|(_, value)| {
+            value
+                .as_str()
+                .is_some_and(|value| value.starts_with("(Binary data"))
+        });
+        for (tag, _) in binary {
+            if let Ok(image) = self.extract_binary(file, tag).await {
+                previews.insert(tag.clone(), image);
+            }
+        }
+        Ok(previews)
+    }
+
+    /// Extracts the embedded `PreviewImage` of `path`. Besides the errors of
+    /// [`ExifTool::extract_binary`], this fails with [`ExifToolError::EmptyBinary`] if the
+    /// preview is only zero-filled padding.
+    pub async fn preview(&self, path: &str) -> Result<Vec<u8>, ExifToolError> {
+        self.extract_image(path, "PreviewImage").await
+    }
+
+    /// Extracts the embedded `ThumbnailImage` of `path`, as with [`ExifTool::preview`].
+    pub async fn thumbnail(&self, path: &str) -> Result<Vec<u8>, ExifToolError> {
+        self.extract_image(path, "ThumbnailImage").await
+    }
+
+    /// Asks the underlying exiftool process to exit, killing it if it does not within a
+    /// second, and reports any error encountered. Dropping an `ExifTool` also shuts the
+    /// process down, but silently.
+    pub async fn close(mut self) -> Result<(), ExifToolError> {
+        let process = self.process.get_mut();
+        if !request_exit(process)? {
+            wait_or_kill(process).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for ExifTool {
+    fn drop(&mut self) {
+        // SAFETY: `process` is never used again, and isn't dropped anywhere else.
+        let mut process = unsafe { ManuallyDrop::take(&mut self.process) }.into_inner();
+        if let Ok(true) = request_exit(&mut process) {
+            return;
+        }
+        match tokio::runtime::Handle::try_current() {
+            // Waiting here would hold up the runtime's other tasks, so a task of its own
+            // waits instead. If the runtime shuts down first, the process is killed as the
+            // task is dropped.
+            Ok(runtime) => {
+                runtime.spawn(async move {
+                    let _ = wait_or_kill(&mut process).await;
+                });
+            }
+            Err(_) => {
+                let _ = wait_or_kill_blocking(&mut process);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Default for ExifTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers(with_status: bool) -> Markers {
+        Markers {
+            ready: "{ready7}".to_string(),
+            err_post: "post7".to_string(),
+            with_status,
+        }
+    }
+
+    fn response(stdout: &[u8], stderr: &[u8], with_status: bool) -> ExifToolOutput {
+        parse_response(stdout.to_vec(), stderr.to_vec(), &markers(with_status)).unwrap()
+    }
+
+    #[test]
+    fn parse_response_empty_stdout() {
+        let output = response(b"{ready7}\n", b"=0=post7\n", true);
+        assert_eq!(output.status, 0);
+        assert!(output.output.is_empty());
+        assert!(output.error.is_empty());
+    }
+
+    #[test]
+    fn parse_response_status_2() {
+        let output = response(
+            b"{ready7}\n",
+            b"    1 files failed condition\n=2=post7\n",
+            true,
+        );
+        assert_eq!(output.status, 2);
+        assert!(!output.is_success());
+        assert_eq!(output.error, b"    1 files failed condition\n");
+    }
+
+    #[test]
+    fn parse_response_trailing_whitespace() {
+        let output = response(
+            b"[{\"Make\": \"Canon\"}]\n{ready7}\n \t\n",
+            b"Warning: odd tag\n=0=post7\n\n  ",
+            true,
+        );
+        assert_eq!(output.output, b"[{\"Make\": \"Canon\"}]\n");
+        assert_eq!(output.error, b"Warning: odd tag\n");
+        assert_eq!(output.warnings, ["odd tag"]);
+    }
+
+    #[test]
+    fn parse_response_without_status() {
+        let output = response(b"{ready7}\n", b"Warning: odd tag\npost7\n", false);
+        assert_eq!(output.status, 0);
+        assert_eq!(output.error, b"Warning: odd tag\n");
+
+        let output = response(
+            b"{ready7}\n",
+            b"Error: File not found - a.jpg\npost7\n",
+            false,
+        );
+        assert_eq!(output.status, 1);
+        assert_eq!(output.error, b"Error: File not found - a.jpg\n");
+    }
+
+    #[test]
+    fn trim_end_strips_crlf() {
+        let trimmed = |bytes: &[u8]| {
+            let mut bytes = bytes.to_vec();
+            trim_end(&mut bytes);
+            bytes
+        };
+        assert_eq!(trimmed(b"{ready7}\r\n"), b"{ready7}");
+        assert_eq!(trimmed(b"a \t\r\n\r\n"), b"a");
+        assert_eq!(trimmed(b"a\r\nb"), b"a\r\nb");
+        assert_eq!(trimmed(b"x"), b"x");
+        assert_eq!(trimmed(b"\r\n"), b"");
+        assert_eq!(trimmed(b""), b"");
+    }
+
+    #[test]
+    fn parse_response_crlf() {
+        let output = response(
+            b"[{\"Make\": \"Canon\"}]\r\n{ready7}\r\n",
+            b"Warning: odd tag\r\n=0=post7\r\n",
+            true,
+        );
+        assert_eq!(output.status, 0);
+        assert_eq!(output.output, b"[{\"Make\": \"Canon\"}]\r\n");
+        assert_eq!(output.warnings, ["odd tag"]);
+
+        let output = response(b"{ready7}\r\n", b"=1=post7\r\n", true);
+        assert_eq!(output.status, 1);
+        assert!(output.output.is_empty());
+        assert!(output.error.is_empty());
+
+        let output = response(b"{ready7}\r\n", b"Error: bad\r\npost7\r\n", false);
+        assert_eq!(output.status, 1);
+        assert_eq!(output.error, b"Error: bad\r\n");
+    }
+
+    #[test]
+    fn parse_stderr_warning_with_equals() {
+        let stderr = b"Warning: [minor] Bad value a=1 =2= for Tag\n=0=post7\n".to_vec();
+        let (status, error) = parse_stderr(stderr, &markers(true)).unwrap();
+        assert_eq!(status, 0);
+        assert_eq!(error, b"Warning: [minor] Bad value a=1 =2= for Tag\n");
+
+        // A warning which ends in `=` isn't taken as the end of the status block.
+        let stderr = b"Warning: x=\n=1=post7\n".to_vec();
+        let (status, error) = parse_stderr(stderr, &markers(true)).unwrap();
+        assert_eq!(status, 1);
+        assert_eq!(error, b"Warning: x=\n");
+
+        // Nor is a block which doesn't start its own line.
+        let stderr = b"Warning: x=2=post7\n".to_vec();
+        let result = parse_stderr(stderr, &markers(true));
+        assert!(matches!(result, Err(ExifToolError::StatusParse(_))));
+    }
+
+    #[test]
+    fn parse_stderr_status_out_of_range() {
+        let stderr = b"Warning: x\n=99999999999=post7\n".to_vec();
+        let result = parse_stderr(stderr, &markers(true));
+        match result {
+            Err(ExifToolError::StatusParse(bytes)) => assert_eq!(bytes, b"99999999999"),
+            result => panic!("{result:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_stderr_warning_after_status() {
+        let stderr = b"Warning: before\n=0=post7\nWarning: after\n".to_vec();
+        let (status, error) = parse_stderr(stderr, &markers(true)).unwrap();
+        assert_eq!(status, 0);
+        assert_eq!(error, b"Warning: before\nWarning: after");
+
+        let stderr = b"=2=post7\r\nWarning: after\r\n".to_vec();
+        let (status, error) = parse_stderr(stderr, &markers(true)).unwrap();
+        assert_eq!(status, 2);
+        assert_eq!(error, b"Warning: after");
+
+        let stderr = b"post7\nError: after\n".to_vec();
+        let (status, error) = parse_stderr(stderr, &markers(false)).unwrap();
+        assert_eq!(status, 1);
+        assert_eq!(error, b"Error: after");
+    }
+
+    #[test]
+    fn old_constant_marker_in_output() {
+        let (message, markers) = ExifToolBuilder::new()
+            .command(vec!["a.jpg".to_string()], 42, true)
+            .unwrap();
+        assert_eq!(markers.ready, "{ready42}");
+        assert!(message.ends_with(b"\n-execute42\n"));
+
+        let json = b"[{\"Comment\": \"{ready193280}\\n-execute193280\"}]\n".to_vec();
+        let mut stdout = json.clone();
+        stdout.extend_from_slice(b"{ready42}\n");
+        let stderr = b"Warning: {ready193280}\n=0=post42\n".to_vec();
+        let output = parse_response(stdout, stderr, &markers).unwrap();
+        assert_eq!(output.output, json);
+        assert_eq!(output.warnings, ["{ready193280}"]);
+    }
+
+    #[test]
+    fn signal_nums_differ() {
+        assert_ne!(initial_signal_num(), initial_signal_num());
+        let builder = ExifToolBuilder::new();
+        let (_, first) = builder.command(Vec::new(), 1, true).unwrap();
+        let (_, second) = builder.command(Vec::new(), 2, true).unwrap();
+        assert_ne!(first.ready, second.ready);
+        assert_ne!(first.err_post, second.err_post);
+    }
+
+    #[test]
+    fn command_rejects_line_breaks() {
+        let builder = ExifToolBuilder::new();
+        for file in ["a\nb.jpg", "a\rb.jpg", "a.jpg\n-execute"] {
+            let result = builder.command(vec!["-j".to_string(), file.to_string()], 1, true);
+            assert!(matches!(result, Err(ExifToolError::InvalidArgument(_))));
+        }
+        let (message, _) = builder
+            .command(vec!["-Comment=a b".to_string()], 1, true)
+            .unwrap();
+        assert!(message.starts_with(b"-Comment=a b\n-echo4\n"));
+    }
+
+    #[cfg(feature = "async")]
+    fn tags(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(tags) => tags,
+            _ => unreachable!(),
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn find_tag_with_groups() {
+        let plain = tags(serde_json::json!({"SourceFile": "a.jpg", "Make": "Canon"}));
+        assert_eq!(find_tag(&plain, "Make"), Some(&Value::from("Canon")));
+        for key in ["EXIF:Make", "IFD0:Make", "EXIF:IFD0:Make"] {
+            let grouped = tags(serde_json::json!({"SourceFile": "a.jpg", key: "Canon"}));
+            assert_eq!(find_tag(&grouped, "Make"), Some(&Value::from("Canon")));
+            assert_eq!(find_tag(&grouped, "Model"), None);
+        }
+        // Only whole names match.
+        let grouped = tags(serde_json::json!({"MakerNotes:LensMake": "Canon"}));
+        assert_eq!(find_tag(&grouped, "Make"), None);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn find_tag_with_duplicates() {
+        // As exiftool prints them with `-a -G1`.
+        let duplicates = tags(serde_json::json!({
+            "SourceFile": "a.jpg",
+            "ExifIFD:ISO": 200,
+            "XMP-exif:ISO": 400,
+            "Composite:GPSLatitude": -33.5,
+            "GPS:GPSLatitude": 33.5,
+        }));
+        assert_eq!(find_tag(&duplicates, "ISO"), Some(&Value::from(200)));
+        assert_eq!(
+            find_tag(&duplicates, "GPSLatitude"),
+            Some(&Value::from(-33.5))
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn gps_from_grouped_tags() {
+        let grouped = tags(serde_json::json!({
+            "SourceFile": "a.jpg",
+            "GPS:GPSLatitude": 33.5,
+            "GPS:GPSLatitudeRef": "S",
+            "GPS:GPSLongitude": "151.25",
+            "GPS:GPSLongitudeRef": "E",
+        }));
+        assert_eq!(gps_from_tags(&grouped), Some((-33.5, 151.25)));
+
+        let plain = tags(serde_json::json!({"GPSLatitude": 10.0, "GPSLongitude": 20.0}));
+        assert_eq!(gps_from_tags(&plain), Some((10.0, 20.0)));
+        assert_eq!(gps_from_tags(&tags(serde_json::json!({}))), None);
+    }
+
+    fn strings(args: Vec<OsString>) -> Vec<String> {
+        args.into_iter()
+            .map(|arg| arg.into_string().unwrap())
+            .collect()
+    }
+
+    /// A reader which returns `chunks` one read at a time, as a pipe might.
+    #[cfg(feature = "async")]
+    struct Chunks(std::collections::VecDeque<Vec<u8>>);
+
+    #[cfg(feature = "async")]
+    impl Chunks {
+        fn new(chunks: &[&[u8]]) -> Self {
+            Chunks(chunks.iter().map(|chunk| chunk.to_vec()).collect())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl AsyncRead for Chunks {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<io::Result<()>> {
+            if let Some(mut chunk) = self.0.pop_front() {
+                let n = chunk.len().min(buf.remaining());
+                buf.put_slice(&chunk[..n]);
+                if n < chunk.len() {
+                    self.0.push_front(chunk.split_off(n));
+                }
+            }
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    /// The marker split across reads in different ways, followed by the start of a later
+    /// response, which must be left unread.
+    #[cfg(feature = "async")]
+    const SPLIT_MARKERS: &[&[&[u8]]] = &[
+        &[b"[1]\n{rea", b"dy7}\n", b"next"],
+        &[b"[1]\n{ready7", b"}", b"\n", b"next"],
+        &[b"[1]\n", b"{ready7}", b"\n", b"next"],
+        &[b"[1]\n{", b"ready7}\n", b"next"],
+    ];
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn read_fd_ends_with_split_marker() {
+        for chunks in SPLIT_MARKERS {
+            for block_size in [1, 3, 64] {
+                let mut reader = Chunks::new(chunks);
+                let output = read_fd_ends_with(&mut reader, "{ready7}", block_size)
+                    .await
+                    .unwrap();
+                assert_eq!(output, b"[1]\n{ready7}\n");
+                assert_eq!(reader.0, [b"next".to_vec()]);
+            }
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn stream_fd_ends_with_split_marker() {
+        for chunks in SPLIT_MARKERS {
+            for block_size in [1, 3, 64] {
+                let mut reader = Chunks::new(chunks);
+                let mut written = Vec::new();
+                let n = stream_fd_ends_with(&mut reader, "{ready7}", block_size, &mut written)
+                    .await
+                    .unwrap();
+                assert_eq!(written, b"[1]\n");
+                assert_eq!(n, 4);
+                assert_eq!(reader.0, [b"next".to_vec()]);
+            }
+        }
+    }
+
+    #[test]
+    fn end_marker_needs_newline() {
+        assert_eq!(find_end_marker(b"[1]\n{ready7}\n", "{ready7}"), Some(4));
+        assert_eq!(find_end_marker(b"[1]\n{ready7}\r\n", "{ready7}"), Some(4));
+        assert_eq!(find_end_marker(b"[1]\n{ready7}", "{ready7}"), None);
+        assert_eq!(find_end_marker(b"=0=post7\nWarning: x\n", "post7"), Some(3));
+        assert_eq!(find_end_marker(b"{ready70}\n", "{ready7}"), None);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn read_fd_ends_with_diagnostics_after_marker() {
+        let chunks: &[&[&[u8]]] = &[
+            &[b"=0=post7\nWarning: late\n", b"next"],
+            &[b"=0=pos", b"t7\nWarning: late\n", b"next"],
+            &[b"=0=post7", b"\nWarning: late\n", b"next"],
+        ];
+        for chunks in chunks {
+            let mut reader = Chunks::new(chunks);
+            let stderr = read_fd_ends_with(&mut reader, "post7", 64).await.unwrap();
+            assert_eq!(stderr, b"=0=post7\nWarning: late\n");
+            assert_eq!(reader.0, [b"next".to_vec()]);
+            let (status, error) = parse_stderr(stderr, &markers(true)).unwrap();
+            assert_eq!((status, error), (0, b"Warning: late".to_vec()));
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn read_fd_ends_with_eof() {
+        let reader = Chunks::new(&[b"[1]\n{rea"]);
+        let result = read_fd_ends_with(reader, "{ready7}", 64).await;
+        assert!(matches!(result, Err(ExifToolError::ProcessGone)));
+    }
+
+    #[cfg(feature = "async")]
+    /// Runs `retry_transient` against an attempt which fails with each of `failures` in turn
+    /// before succeeding, returning the result and how many attempts and resets it took.
+    async fn retry_with_failures(
+        retry: Option<(u32, Duration)>,
+        failures: Vec<ExifToolError>,
+    ) -> (Result<&'static str, ExifToolError>, usize, usize) {
+        let failures = std::sync::Mutex::new(failures.into_iter());
+        let (attempts, resets) = (AtomicU64::new(0), AtomicU64::new(0));
+        let result = retry_transient(
+            retry,
+            || {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                let failure = failures.lock().unwrap().next();
+                async move { failure.map_or(Ok("output"), Err) }
+            },
+            || {
+                resets.fetch_add(1, Ordering::Relaxed);
+                async { Ok(()) }
+            },
+        )
+        .await;
+        let count = |n: AtomicU64| n.into_inner() as usize;
+        (result, count(attempts), count(resets))
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn retry_after_transient_failures() {
+        let retry = Some((3, Duration::from_millis(1)));
+        for failure in [
+            ExifToolError::Timeout,
+            ExifToolError::ProcessGone,
+            ExifToolError::Io(io::ErrorKind::UnexpectedEof.into()),
+        ] {
+            let (result, attempts, resets) = retry_with_failures(retry, vec![failure]).await;
+            assert_eq!(result.unwrap(), "output");
+            assert_eq!((attempts, resets), (2, 1));
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn retry_gives_up_after_max_attempts() {
+        let failures = vec![ExifToolError::Timeout, ExifToolError::Timeout];
+        let (result, attempts, resets) =
+            retry_with_failures(Some((2, Duration::from_millis(1))), failures).await;
+        assert!(matches!(result, Err(ExifToolError::Timeout)));
+        assert_eq!((attempts, resets), (2, 1));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn retry_skips_reported_errors() {
+        let failures = vec![ExifToolError::MissingTag("Make".to_string())];
+        let (result, attempts, resets) =
+            retry_with_failures(Some((3, Duration::from_millis(1))), failures).await;
+        assert!(matches!(result, Err(ExifToolError::MissingTag(_))));
+        assert_eq!((attempts, resets), (1, 0));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn retry_unset() {
+        let (result, attempts, resets) =
+            retry_with_failures(None, vec![ExifToolError::Timeout]).await;
+        assert!(matches!(result, Err(ExifToolError::Timeout)));
+        assert_eq!((attempts, resets), (1, 0));
+    }
+
+    #[test]
+    fn launch_args_default() {
+        let args = strings(ExifToolBuilder::new().launch_args(Launch::StayOpen));
+        assert_eq!(args, ["-stay_open", "True", "-@", "-"]);
+    }
+
+    #[test]
+    fn launch_args_stay_open() {
+        let builder = ExifToolBuilder::new()
+            .arg("-d".to_string())
+            .api_option("QuickTimeUTC", 1)
+            .group_mode(GroupMode::Group0)
+            .filename_charset("utf8")
+            .config_file("user.config")
+            .charset("latin")
+            .numeric(true)
+            .api_option("quicktimeutc", 0);
+        let args = strings(builder.launch_args(Launch::StayOpen));
+        assert_eq!(
+            args,
+            [
+                "-config",
+                "user.config",
+                "-stay_open",
+                "True",
+                "-@",
+                "-",
+                "-common_args",
+                "-charset",
+                "latin",
+                "-charset",
+                "filename=utf8",
+                "-G0",
+                "-n",
+                "-api",
+                "QuickTimeUTC=0",
+                "-d",
+            ]
+        );
+    }
+
+    #[test]
+    fn launch_args_allow_duplicates() {
+        let args = strings(
+            ExifToolBuilder::new()
+                .allow_duplicates(true)
+                .launch_args(Launch::StayOpen),
+        );
+        assert_eq!(&args[4..], ["-common_args", "-G1", "-a"]);
+
+        // An explicit group mode is kept.
+        let args = strings(
+            ExifToolBuilder::new()
+                .allow_duplicates(true)
+                .group_mode(GroupMode::Combined)
+                .launch_args(Launch::StayOpen),
+        );
+        assert_eq!(&args[4..], ["-common_args", "-G0:1", "-a"]);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn launch_args_once() {
+        let builder = ExifToolBuilder::new()
+            .config_file("user.config")
+            .filename_charset("utf8")
+            .ignore_minor_errors(true)
+            .api_option("LargeFileSupport", 1)
+            .arg("-fast".to_string());
+        let launch = Launch::Once(vec!["-j".to_string(), "-".to_string()]);
+        let args = strings(builder.launch_args(launch));
+        assert_eq!(
+            args,
+            [
+                "-config",
+                "user.config",
+                "-charset",
+                "filename=utf8",
+                "-m",
+                "-api",
+                "LargeFileSupport=1",
+                "-fast",
+                "-j",
+                "-",
+            ]
+        );
+
+        let args =
+            strings(ExifToolBuilder::new().launch_args(Launch::Once(vec!["-ver".to_string()])));
+        assert_eq!(args, ["-ver"]);
+    }
+
+    #[test]
+    fn parse_response_missing_markers() {
+        let result = parse_response(b"[]\n".to_vec(), b"=0=post7\n".to_vec(), &markers(true));
+        assert!(matches!(result, Err(ExifToolError::MissingReadyMarker)));
+        let result = parse_response(b"{ready7}\n".to_vec(), b"=0=\n".to_vec(), &markers(true));
+        assert!(matches!(result, Err(ExifToolError::MissingReadyMarker)));
+    }
+
+    #[test]
+    fn write_summary_single_file() {
+        let summary = WriteSummary::parse(b"    1 image files updated\n");
+        assert_eq!(
+            (summary.updated, summary.unchanged, summary.errors),
+            (1, 0, 0)
+        );
+    }
+
+    #[test]
+    fn write_summary_several_files() {
+        let output = b"    1 directories scanned\n    3 image files updated\n    \
+            2 image files unchanged\n    1 files weren't updated due to errors\n";
+        let summary = WriteSummary::parse(output);
+        assert_eq!(
+            (summary.updated, summary.unchanged, summary.errors),
+            (3, 2, 1)
+        );
+
+        let summary =
+            WriteSummary::parse(b"    2 image files created\n    1 output files created\n");
+        assert_eq!(summary.updated, 3);
+        assert_eq!(WriteSummary::parse(b""), WriteSummary::default());
+    }
+
+    #[test]
+    fn write_summary_counts_both_streams() {
+        let output = ExifToolOutput::new(
+            1,
+            b"    1 image files updated\n".to_vec(),
+            b"Warning: [minor] Odd offset - b.jpg\nError: File not found - c.jpg\n    \
+            1 files weren't updated due to errors\n"
+                .to_vec(),
+        );
+        let summary = output.write_summary();
+        assert_eq!((summary.updated, summary.errors), (1, 1));
+        assert_eq!(summary.warnings, ["[minor] Odd offset - b.jpg"]);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn file_failures_named_in_errors() {
+        let error = b"Error: File not found - c.jpg\nError: Not a valid JPG - b.jpg\n";
+        let output = ExifToolOutput::new(1, Vec::new(), error.to_vec());
+        let failures = output.file_failures(&["a.jpg", "c.jpg"]);
+        assert_eq!(
+            failures,
+            [(PathBuf::from("c.jpg"), "File not found".to_string())]
+        );
+    }
+
+    #[test]
+    fn reported_error_minor_or_fatal() {
+        let error = |stderr: &[u8]| ExifToolOutput::new(1, Vec::new(), stderr.to_vec());
+        let minor = b"Error: [minor] Bad format (0) for MakerNotes entry 1 - a.jpg\n";
+        match error(minor).reported_error() {
+            ExifToolError::MinorError(message) => assert!(message.contains("[minor]")),
+            e => panic!("{e:?}"),
+        }
+        // A single fatal error makes the call fail as a whole, whatever else is minor.
+        let mixed = b"Error: [minor] Bad format - a.jpg\nError: Not a valid JPG - b.jpg\n";
+        match error(mixed).reported_error() {
+            ExifToolError::Reported(message) => assert_eq!(message.lines().count(), 2),
+            e => panic!("{e:?}"),
+        }
+        // Warnings alone, even minor ones, aren't what made the call fail.
+        let warning = b"Warning: [minor] Odd offset - a.jpg\nError: Not a valid JPG - a.jpg\n";
+        assert!(matches!(
+            error(warning).reported_error(),
+            ExifToolError::Reported(_)
+        ));
+    }
+
+    #[test]
+    fn reported_error_missing_files() {
+        let error = |stderr: &[u8]| ExifToolOutput::new(1, Vec::new(), stderr.to_vec());
+        let missing = b"Error: File not found - a b.jpg\nError: File not found - c.jpg\n";
+        match error(missing).reported_error() {
+            ExifToolError::FileNotFound(path) => assert_eq!(path, Path::new("a b.jpg")),
+            e => panic!("{e:?}"),
+        }
+        // The error of a file which was found says more than that another one wasn't.
+        let mixed = b"Error: File not found - a.jpg\nError: Not a valid JPG - b.jpg\n";
+        assert!(matches!(
+            error(mixed).reported_error(),
+            ExifToolError::Reported(_)
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn parse_rational_fractions() {
+        let rational = |value: Value| parse_rational(&value);
+        assert_eq!(rational("1/250".into()), Some((1, 250)));
+        assert_eq!(rational(" +2/3 ".into()), Some((2, 3)));
+        assert_eq!(rational("-1/3".into()), Some((-1, 3)));
+        assert_eq!(rational("1/0".into()), None);
+        assert_eq!(rational("a/b".into()), None);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn parse_rational_decimals() {
+        let rational = |value: Value| parse_rational(&value);
+        assert_eq!(rational("0.125".into()), Some((125, 1000)));
+        assert_eq!(rational("-1.5".into()), Some((-15, 10)));
+        assert_eq!(rational("-0.5".into()), Some((-5, 10)));
+        assert_eq!(rational(serde_json::json!(4)), Some((4, 1)));
+        assert_eq!(rational(serde_json::json!(0.3)), Some((3, 10)));
+        assert_eq!(rational("1.5e3".into()), None);
+        assert_eq!(rational("inf".into()), None);
+        assert_eq!(rational(Value::Null), None);
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn compact_json_outside_strings() {
+        let json = "[{\n  \"SourceFile\": \"my photo.jpg\",\n  \"Sizes\": [ 1, 2 ]\n}]\n";
+        assert_eq!(
+            compact_json(json),
+            r#"[{"SourceFile":"my photo.jpg","Sizes":[1,2]}]"#
+        );
+        // Whitespace is kept in strings, including after escaped quotes and backslashes.
+        let json = r#"{ "A": "say \"hi there\"", "B": "C:\\ x", "C" : 1 }"#;
+        assert_eq!(
+            compact_json(json),
+            r#"{"A":"say \"hi there\"","B":"C:\\ x","C":1}"#
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn check_updated_accepts_unchanged() {
+        let output = |status, stdout: &[u8], stderr: &[u8]| {
+            ExifToolOutput::new(status, stdout.to_vec(), stderr.to_vec())
+        };
+        let summary = output(0, b"    1 image files updated\n", b"")
+            .check_updated()
+            .unwrap();
+        assert_eq!((summary.updated, summary.unchanged), (1, 0));
+        let summary = output(0, b"    1 image files unchanged\n", b"")
+            .check_updated()
+            .unwrap();
+        assert_eq!((summary.updated, summary.unchanged), (0, 1));
+
+        let nothing = output(0, b"", b"Warning: Nothing to do.\n").check_updated();
+        assert!(matches!(nothing, Err(ExifToolError::Reported(_))));
+        let failed = output(
+            1,
+            b"    0 image files updated\n",
+            b"Error: Not a valid JPG - a.jpg\n    1 files weren't updated due to errors\n",
+        )
+        .check_updated();
+        assert!(matches!(failed, Err(ExifToolError::Reported(_))));
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn list_separator_from_args() {
+        assert_eq!(ExifToolBuilder::new().list_separator(), None);
+        let builder = ExifToolBuilder::new()
+            .arg("-n".to_string())
+            .arg("-sep".to_string())
+            .arg("; ".to_string());
+        assert_eq!(builder.list_separator(), Some("; "));
+    }
+
+    #[test]
+    fn decode_base64_tags() {
+        let value = Value::String("base64:/9j/4A==".to_string());
+        assert_eq!(
+            decode_base64_tag(&value),
+            Some(vec![0xff, 0xd8, 0xff, 0xe0])
+        );
+        // Text values are printed as is, even if they happen to be valid base64.
+        assert_eq!(
+            decode_base64_tag(&Value::String("/9j/4A==".to_string())),
+            None
+        );
+        assert_eq!(
+            decode_base64_tag(&Value::String("base64:not base64!".to_string())),
+            None
+        );
+        assert_eq!(decode_base64_tag(&Value::from(16)), None);
     }
 }
@@ -0,0 +1,138 @@
+use std::ops::Deref;
+use std::path::PathBuf;
+
+use futures_util::{Stream, StreamExt};
+use serde_json::Value;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::{ExifTool, ExifToolBuilder, ExifToolError, ExifToolOutput};
+
+/// A fixed set of exiftool processes which can serve calls in parallel.
+///
+/// Each call runs on the first idle process, waiting for one to become idle if all are busy.
+pub struct ExifToolPool {
+    tools: Vec<ExifTool>,
+    idle: std::sync::Mutex<Vec<usize>>,
+    available: Semaphore,
+}
+
+/// An [`ExifTool`] checked out of an [`ExifToolPool`], which is returned to the pool when
+/// dropped.
+pub struct PooledExifTool<'a> {
+    pool: &'a ExifToolPool,
+    index: usize,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl ExifToolPool {
+    /// Spawns `size` exiftool processes with the default configuration.
+    ///
+    /// # Panics
+    /// Panics if `size` is zero or exiftool could not be spawned. Use
+    /// [`ExifToolPool::try_new`] to handle the latter.
+    pub fn new(size: usize) -> Self {
+        Self::try_new(size).unwrap()
+    }
+
+    pub fn try_new(size: usize) -> Result<Self, ExifToolError> {
+        Self::from_builder(ExifToolBuilder::new(), size)
+    }
+
+    /// Spawns `size` exiftool processes, each configured by `builder`.
+    ///
+    /// # Panics
+    /// Panics if `size` is zero.
+    pub fn from_builder(builder: ExifToolBuilder, size: usize) -> Result<Self, ExifToolError> {
+        assert!(size > 0, "an ExifToolPool needs at least one process");
+        let tools = (0..size)
+            .map(|_| builder.clone().build())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ExifToolPool {
+            tools,
+            idle: std::sync::Mutex::new((0..size).rev().collect()),
+            available: Semaphore::new(size),
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.tools.len()
+    }
+
+    /// Waits for an idle process and checks it out, giving access to the full [`ExifTool`]
+    /// API. Holding onto it keeps the process unavailable to other callers.
+    pub async fn get(&self) -> PooledExifTool<'_> {
+        let permit = self
+            .available
+            .acquire()
+            .await
+            .expect("the pool semaphore is never closed");
+        let index = self
+            .idle
+            .lock()
+            .unwrap()
+            .pop()
+            .expect("a permit guarantees an idle process");
+        PooledExifTool {
+            pool: self,
+            index,
+            _permit: permit,
+        }
+    }
+
+    pub async fn execute(&self, params: Vec<String>) -> Result<ExifToolOutput, ExifToolError> {
+        self.get().await.execute(params).await
+    }
+
+    pub async fn execute_json(&self, params: Vec<String>) -> Result<Value, ExifToolError> {
+        self.get().await.execute_json(params).await
+    }
+
+    pub async fn get_tags(
+        &self,
+        params: Vec<String>,
+        tags: Vec<String>,
+        files: Vec<String>,
+    ) -> Result<Value, ExifToolError> {
+        self.get().await.get_tags(params, tags, files).await
+    }
+
+    pub async fn preview(&self, path: &str) -> Result<Vec<u8>, ExifToolError> {
+        self.get().await.preview(path).await
+    }
+
+    /// Reads the metadata of each of `files`, with up to `concurrency` of them in flight
+    /// at once, yielding each with its path as soon as it is read, in whatever order they
+    /// finish. Files are only taken from `files` as earlier ones finish, so a slow consumer
+    /// holds the rest back rather than letting results pile up.
+    ///
+    /// A `concurrency` above [`ExifToolPool::size`] gains nothing, as the extra files wait
+    /// for an idle process. A `concurrency` of zero is treated as one.
+    pub fn process_files(
+        &self,
+        files: Vec<PathBuf>,
+        concurrency: usize,
+    ) -> impl Stream<Item = (PathBuf, Result<Value, ExifToolError>)> + '_ {
+        futures_util::stream::iter(files)
+            .map(move |file| async move {
+                let params = vec![file.to_string_lossy().into_owned()];
+                let result = self.get().await.execute_struct_one(params).await;
+                (file, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+    }
+}
+
+impl Deref for PooledExifTool<'_> {
+    type Target = ExifTool;
+
+    fn deref(&self) -> &ExifTool {
+        &self.pool.tools[self.index]
+    }
+}
+
+impl Drop for PooledExifTool<'_> {
+    fn drop(&mut self) {
+        // The permit is released after this, so the index is idle before anyone can take it.
+        self.pool.idle.lock().unwrap().push(self.index);
+    }
+}
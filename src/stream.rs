@@ -0,0 +1,331 @@
+use bstr::ByteSlice;
+use futures_util::Stream;
+use serde_json::Value;
+use tokio::io::AsyncReadExt;
+use tokio::process::Child;
+use tokio::sync::MutexGuard;
+
+use crate::{find_end_marker, parse_stderr, ExifTool, ExifToolError, ExifToolOutput, Markers};
+
+/// Where an [`ArraySplitter`] is in exiftool's JSON array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArrayState {
+    /// Before the opening `[`. exiftool prints no array at all if it read no files.
+    Before,
+    Inside,
+    /// After the closing `]`, or if there was no array, where only the ready marker is left.
+    After,
+}
+
+/// Splits the elements out of exiftool's JSON array as its output arrives.
+struct ArraySplitter {
+    /// Output which hasn't been split off yet.
+    buf: Vec<u8>,
+    /// How much of `buf` has been scanned.
+    scanned: usize,
+    state: ArrayState,
+    /// How deeply nested the scan is inside the current element.
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Where the current element starts in `buf`.
+    start: usize,
+}
+
+impl ArraySplitter {
+    fn new() -> Self {
+        ArraySplitter {
+            buf: Vec::new(),
+            scanned: 0,
+            state: ArrayState::Before,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            start: 0,
+        }
+    }
+
+    /// Scans the output received so far for the next complete element, removing it and
+    /// everything before it from the buffer. Fails on a bracket which closes nothing.
+    fn next_element(&mut self) -> Result<Option<Vec<u8>>, ExifToolError> {
+        while self.scanned < self.buf.len() {
+            let b = self.buf[self.scanned];
+            match self.state {
+                ArrayState::Before => {
+                    if b == b'[' {
+                        self.state = ArrayState::Inside;
+                    } else if !b.is_ascii_whitespace() {
+                        self.state = ArrayState::After;
+                        return Ok(None);
+                    }
+                    self.scanned += 1;
+                    continue;
+                }
+                ArrayState::After => return Ok(None),
+                ArrayState::Inside => self.scanned += 1,
+            }
+
+            if self.in_string {
+                match b {
+                    _ if self.escaped => self.escaped = false,
+                    b'\\' => self.escaped = true,
+                    b'"' => self.in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match b {
+                b'"' => self.in_string = true,
+                b'{' | b'[' => {
+                    if self.depth == 0 {
+                        self.start = self.scanned - 1;
+                    }
+                    self.depth += 1;
+                }
+                b']' if self.depth == 0 => self.state = ArrayState::After,
+                b'}' if self.depth == 0 => return Err(malformed("a `}` which closes nothing")),
+                b'}' | b']' => {
+                    self.depth -= 1;
+                    if self.depth == 0 {
+                        let element = self.buf[self.start..self.scanned].to_vec();
+                        self.buf.drain(..self.scanned);
+                        self.scanned = 0;
+                        return Ok(Some(element));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether the output received so far ends with `ready`. Fails if it arrived before the
+    /// array was closed, as the output was then cut short.
+    fn ended(&self, ready: &str) -> Result<bool, ExifToolError> {
+        if find_end_marker(&self.buf, ready).is_none() {
+            return Ok(false);
+        }
+        if self.state == ArrayState::Inside {
+            return Err(malformed("the ready marker before the end of the array"));
+        }
+        Ok(true)
+    }
+}
+
+/// An error for JSON output from exiftool which has `found` where it can't be.
+fn malformed(found: &str) -> ExifToolError {
+    let message = format!("exiftool's JSON output has {found}");
+    ExifToolError::Json(serde::de::Error::custom(message))
+}
+
+/// The state of a stream returned by [`ExifTool::execute_json_stream`], which holds the
+/// process for as long as it is read.
+struct JsonStream<'a> {
+    tool: &'a ExifTool,
+    process: MutexGuard<'a, Child>,
+    markers: Markers,
+    stdout: ArraySplitter,
+    stderr: Vec<u8>,
+    stdout_block: Vec<u8>,
+    stderr_block: Vec<u8>,
+    yielded: usize,
+    finished: bool,
+}
+
+impl JsonStream<'_> {
+    async fn next(&mut self) -> Option<Result<Value, ExifToolError>> {
+        if self.finished {
+            return None;
+        }
+        let next = self.advance().await;
+        if !matches!(next, Ok(Some(_))) {
+            self.finished = true;
+        }
+        next.transpose()
+    }
+
+    /// Reads until the next element is complete, or the response has ended.
+    async fn advance(&mut self) -> Result<Option<Value>, ExifToolError> {
+        let JsonStream {
+            tool,
+            process,
+            markers,
+            stdout,
+            stderr,
+            stdout_block,
+            stderr_block,
+            yielded,
+            ..
+        } = self;
+        loop {
+            if let Some(element) = stdout.next_element()? {
+                *yielded += 1;
+                return Ok(Some(serde_json::from_slice(&element)?));
+            }
+            let stdout_done = stdout.ended(&markers.ready)?;
+            let stderr_done = find_end_marker(stderr, &markers.err_post).is_some();
+            if stdout_done && stderr_done {
+                break;
+            }
+
+            let child: &mut Child = process;
+            let stdout_pipe = child.stdout.as_mut().ok_or(ExifToolError::ProcessGone)?;
+            let stderr_pipe = child.stderr.as_mut().ok_or(ExifToolError::ProcessGone)?;
+            // Both pipes are read concurrently so that a full stderr can't block stdout.
+            let read = tokio::select! {
+                n = stdout_pipe.read(stdout_block), if !stdout_done => {
+                    n.map(|n| (n, &mut stdout.buf, &stdout_block[..n]))
+                }
+                n = stderr_pipe.read(stderr_block), if !stderr_done => {
+                    n.map(|n| (n, &mut *stderr, &stderr_block[..n]))
+                }
+            };
+            match read {
+                Ok((n, output, block)) if n > 0 => output.extend_from_slice(block),
+                // The pipes only reach EOF once exiftool has exited.
+                read => {
+                    let _ = process.kill().await;
+                    return Err(read.err().map_or(ExifToolError::ProcessGone, Into::into));
+                }
+            }
+        }
+
+        let (status, error) = parse_stderr(std::mem::take(stderr), markers)?;
+        tool.end_response();
+        if *yielded == 0 && !error.trim().is_empty() {
+            // As with the buffered JSON methods, diagnostics alone are the error.
+            return Err(ExifToolOutput::new(status, Vec::new(), error).reported_error());
+        }
+        Ok(None)
+    }
+}
+
+impl ExifTool {
+    /// Like [`ExifTool::execute_json`], but yields the object for each file as soon as
+    /// exiftool has printed it, rather than buffering the whole array. This keeps memory
+    /// use flat when reading thousands of files, such as with `-r`.
+    ///
+    /// The stream holds the process until it ends, so other calls wait for it. Dropping it
+    /// before then leaves the rest of the response unread, which poisons the process until
+    /// [`ExifTool::drain`] or [`ExifTool::reset`] is called.
+    pub async fn execute_json_stream(
+        &self,
+        mut params: Vec<String>,
+    ) -> Result<impl Stream<Item = Result<Value, ExifToolError>> + '_, ExifToolError> {
+        params.insert(0, "-j".to_string());
+        let (message, markers) = self.command(params, self.status_supported().await?)?;
+        let mut process = self.lock_process().await?;
+        crate::send(&mut process, &message).await?;
+
+        let block_size = self.config.block_size();
+        let stream = JsonStream {
+            tool: self,
+            process,
+            markers,
+            stdout: ArraySplitter::new(),
+            stderr: Vec::new(),
+            stdout_block: vec![0; block_size],
+            stderr_block: vec![0; block_size],
+            yielded: 0,
+            finished: false,
+        };
+        Ok(futures_util::stream::unfold(stream, |mut stream| async {
+            let item = stream.next().await?;
+            Some((item, stream))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `output` to a splitter `chunk_size` bytes at a time, as it might arrive from
+    /// exiftool, returning the elements split off and the splitter.
+    fn split(output: &[u8], chunk_size: usize) -> (Vec<String>, ArraySplitter) {
+        let mut splitter = ArraySplitter::new();
+        let mut elements = Vec::new();
+        for chunk in output.chunks(chunk_size) {
+            splitter.buf.extend_from_slice(chunk);
+            while let Some(element) = splitter.next_element().unwrap() {
+                elements.push(String::from_utf8(element).unwrap());
+            }
+        }
+        (elements, splitter)
+    }
+
+    #[test]
+    fn splits_elements() {
+        let output = b"[{\n  \"SourceFile\": \"a.jpg\",\n  \"Keywords\": [\"x\", \"y\"]\n},\n\
+            {\n  \"SourceFile\": \"b.jpg\",\n  \"Region\": {\"Name\": \"Jane\"}\n}]\n{ready3}\n";
+        for chunk_size in [1, 2, 7, output.len()] {
+            let (elements, splitter) = split(output, chunk_size);
+            let files: Vec<Value> = elements
+                .iter()
+                .map(|element| serde_json::from_str(element).unwrap())
+                .collect();
+            assert_eq!(files.len(), 2, "{chunk_size}");
+            assert_eq!(files[0]["Keywords"][1], "y");
+            assert_eq!(files[1]["Region"]["Name"], "Jane");
+            assert_eq!(splitter.state, ArrayState::After);
+            assert!(find_end_marker(&splitter.buf, "{ready3}").is_some());
+        }
+    }
+
+    #[test]
+    fn brackets_and_quotes_in_strings() {
+        let output = br#"[{"Comment": "}] \"{[\\"}, {"Comment": "\\"}]"#;
+        for chunk_size in [1, output.len()] {
+            let (elements, splitter) = split(output, chunk_size);
+            assert_eq!(
+                elements,
+                [r#"{"Comment": "}] \"{[\\"}"#, r#"{"Comment": "\\"}"#]
+            );
+            assert_eq!(splitter.state, ArrayState::After);
+        }
+    }
+
+    #[test]
+    fn no_array() {
+        let (elements, splitter) = split(b"\n{ready3}\n", 1);
+        assert!(elements.is_empty());
+        assert_eq!(splitter.state, ArrayState::After);
+    }
+
+    #[test]
+    fn incomplete_element() {
+        let (elements, splitter) = split(br#"[{"SourceFile": "a.jpg"}, {"Source"#, 4);
+        assert_eq!(elements, [r#"{"SourceFile": "a.jpg"}"#]);
+        assert_eq!(splitter.state, ArrayState::Inside);
+    }
+
+    #[test]
+    fn unbalanced_brackets() {
+        for output in [&b"[{}}]"[..], b"[}", b"[{\"a\": [1]]}]"] {
+            let mut splitter = ArraySplitter::new();
+            splitter.buf.extend_from_slice(output);
+            let mut result = splitter.next_element();
+            while let Ok(Some(_)) = result {
+                result = splitter.next_element();
+            }
+            assert!(matches!(result, Err(ExifToolError::Json(_))), "{output:?}");
+        }
+    }
+
+    #[test]
+    fn marker_before_end_of_array() {
+        // Cut short inside an element, where the marker looks like more of it.
+        let output = b"[{\"SourceFile\": \"a.jpg\"}, {\"Keywords\": [\"x\"\n{ready3}\n";
+        let (elements, splitter) = split(output, 4);
+        assert_eq!(elements.len(), 1);
+        assert!(matches!(
+            splitter.ended("{ready3}"),
+            Err(ExifToolError::Json(_))
+        ));
+
+        let (_, splitter) = split(b"[{\"SourceFile\": \"a.jpg\"}]\n{ready3}\n", 4);
+        assert!(splitter.ended("{ready3}").unwrap());
+        let (_, splitter) = split(b"[{\"SourceFile\": \"a.jpg\"}]\n{rea", 4);
+        assert!(!splitter.ended("{ready3}").unwrap());
+    }
+}
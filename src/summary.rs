@@ -0,0 +1,95 @@
+use serde_json::Value;
+
+use crate::dimensions::stored_dimensions;
+use crate::{find_tag, gps_from_tags, value_as_f64, ExifTool, ExifToolError, FileType};
+
+/// The most commonly wanted facts about an image, as read by [`ExifTool::summarize`]. Each
+/// is `None` if the file doesn't record it.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImageSummary {
+    /// The width in pixels, as stored, before any rotation. See
+    /// [`ExifTool::display_dimensions`] for the size it is shown at.
+    pub width: Option<u32>,
+    /// The height in pixels, as stored.
+    pub height: Option<u32>,
+    /// The EXIF orientation, from 1 to 8, where 1 is upright.
+    pub orientation: Option<u8>,
+    /// The rotation in degrees recorded by a HEIF container or video.
+    pub rotation: Option<u16>,
+    pub file_type: Option<FileType>,
+    /// The MIME type, such as `image/jpeg`.
+    pub mime: Option<String>,
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    /// The lens model, from whichever of the tags makers use for it is present.
+    pub lens: Option<String>,
+    /// When the photo was taken, as exiftool prints it, such as `2024:05:01 14:30:00`.
+    pub datetime: Option<String>,
+    /// The GPS position in signed decimal degrees, as `(latitude, longitude)`.
+    pub gps: Option<(f64, f64)>,
+}
+
+impl ExifTool {
+    /// Reads the dimensions and rotation, type, camera, lens, capture time and GPS position
+    /// of `file` with a single call.
+    pub async fn summarize(&self, file: &str) -> Result<ImageSummary, ExifToolError> {
+        let tags = self
+            .get_tags_for_file(
+                file,
+                &[
+                    "Composite:ImageSize#",
+                    "ImageWidth#",
+                    "ImageHeight#",
+                    "Orientation#",
+                    "Rotation#",
+                    "FileType",
+                    "MIMEType",
+                    "Make",
+                    "Model",
+                    "LensModel",
+                    "LensID",
+                    "Lens",
+                    "DateTimeOriginal",
+                    "CreateDate",
+                    "GPSLatitude#",
+                    "GPSLongitude#",
+                    "GPSLatitudeRef",
+                    "GPSLongitudeRef",
+                ],
+            )
+            .await?;
+        let text = |tag: &str| {
+            find_tag(&tags, tag)
+                .map(|value| match value {
+                    Value::String(s) => s.trim().to_string(),
+                    value => value.to_string(),
+                })
+                .filter(|s| !s.is_empty())
+        };
+        // Cameras without a set clock write `0000:00:00 00:00:00`.
+        let date = |tag: &str| {
+            text(tag).filter(|date| date.bytes().any(|b| b.is_ascii_digit() && b != b'0'))
+        };
+        let dimensions = stored_dimensions(&tags);
+        Ok(ImageSummary {
+            width: dimensions.map(|(width, _)| width),
+            height: dimensions.map(|(_, height)| height),
+            orientation: find_tag(&tags, "Orientation")
+                .and_then(value_as_f64)
+                .filter(|n| (1.0..=8.0).contains(n))
+                .map(|n| n as u8),
+            rotation: find_tag(&tags, "Rotation")
+                .and_then(value_as_f64)
+                .map(|n| (n.round() as i64).rem_euclid(360) as u16),
+            file_type: text("FileType").map(|name| FileType::from(name.as_str())),
+            mime: text("MIMEType"),
+            camera_make: text("Make"),
+            camera_model: text("Model"),
+            lens: text("LensModel")
+                .or_else(|| text("LensID"))
+                .or_else(|| text("Lens")),
+            datetime: date("DateTimeOriginal").or_else(|| date("CreateDate")),
+            gps: gps_from_tags(&tags),
+        })
+    }
+}
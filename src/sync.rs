@@ -0,0 +1,320 @@
+//! An interface to exiftool which doesn't depend on tokio, for codebases which avoid async.
+//!
+//! This crate has two backends, each behind a feature:
+//!
+//! - `async` (on by default) provides `exiftool::ExifTool`, which drives exiftool with tokio.
+//!   The `blocking` feature wraps it in a runtime of its own for synchronous callers.
+//! - `sync` provides the [`ExifTool`] here, which reads exiftool's output on a thread per
+//!   pipe and passes it back over [`std::sync::mpsc`] channels. With `default-features =
+//!   false`, tokio isn't built at all.
+//!
+//! Both speak the same protocol to exiftool and share [`ExifToolBuilder`] and
+//! [`ExifToolOutput`], but only the async backend has the full set of helpers.
+
+use std::io::{self, Read, Write};
+use std::process::{Child, ChildStdin};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    find_end_marker, initial_signal_num, marker_search_start, parse_response, parse_stdout,
+    parse_version, Capabilities, ExifToolBuilder, ExifToolError, ExifToolOutput, Launch, Markers,
+    SHUTDOWN_TIMEOUT,
+};
+
+/// A `-stay_open` exiftool process, with a thread reading each of its output pipes.
+struct Process {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: Receiver<Vec<u8>>,
+    stderr: Receiver<Vec<u8>>,
+}
+
+impl Process {
+    fn spawn(config: &ExifToolBuilder) -> Result<Self, ExifToolError> {
+        let mut child = config
+            .std_command(Launch::StayOpen)
+            .spawn()
+            .map_err(|source| config.spawn_failed(source))?;
+        let block_size = config.block_size();
+        let stdout = read_in_background(child.stdout.take(), block_size);
+        let stderr = read_in_background(child.stderr.take(), block_size);
+        Ok(Process {
+            stdin: child.stdin.take(),
+            child,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Sends `message` to exiftool and collects stdout and stderr up to their respective
+    /// markers.
+    fn communicate(
+        &mut self,
+        message: &[u8],
+        markers: &Markers,
+    ) -> Result<(Vec<u8>, Vec<u8>), ExifToolError> {
+        let response = (|| {
+            let stdin = self.stdin.as_mut().ok_or(ExifToolError::ProcessGone)?;
+            stdin.write_all(message)?;
+            stdin.flush()?;
+            // Both pipes are drained by their threads, so they can be collected one by one.
+            Ok((
+                read_until(&self.stdout, &markers.ready)?,
+                read_until(&self.stderr, &markers.err_post)?,
+            ))
+        })();
+        if matches!(response, Err(ExifToolError::ProcessGone)) {
+            // Make sure a dying process has exited, so that the next call respawns it.
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+        response
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // exiftool exits once it reaches the end of the `-@ -` argfile.
+        drop(self.stdin.take());
+        let start = Instant::now();
+        while start.elapsed() < SHUTDOWN_TIMEOUT {
+            if !matches!(self.child.try_wait(), Ok(None)) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Reads `pipe` on a thread of its own, sending each chunk as it arrives. The channel is
+/// closed once the pipe reaches EOF, which only happens once exiftool has exited.
+fn read_in_background<R: Read + Send + 'static>(
+    pipe: Option<R>,
+    block_size: usize,
+) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    if let Some(mut pipe) = pipe {
+        std::thread::spawn(move || {
+            let mut buf = vec![0; block_size];
+            loop {
+                match pipe.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        if sender.send(buf[..n].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(_) => break,
+                }
+            }
+        });
+    }
+    receiver
+}
+
+/// Collects chunks from `receiver` until they include `marker` and the newline after it.
+fn read_until(receiver: &Receiver<Vec<u8>>, marker: &str) -> Result<Vec<u8>, ExifToolError> {
+    let mut output = Vec::new();
+    loop {
+        let start = marker_search_start(output.len(), marker);
+        let chunk = receiver.recv().map_err(|_| ExifToolError::ProcessGone)?;
+        output.extend_from_slice(&chunk);
+        if find_end_marker(&output[start..], marker).is_some() {
+            return Ok(output);
+        }
+    }
+}
+
+/// A persistent exiftool process which is used from synchronous code, without tokio.
+///
+/// Calls are serialized by a [`std::sync::Mutex`]. If one panics or fails partway through
+/// talking to exiftool, later calls fail with [`ExifToolError::Poisoned`] until
+/// [`ExifTool::reset`] is called.
+pub struct ExifTool {
+    process: Mutex<Process>,
+    config: ExifToolBuilder,
+    /// Set while a call is talking to exiftool, and left set if it doesn't finish, since
+    /// that can leave unread bytes in the pipes. This, rather than the mutex's own
+    /// poisoning, decides whether the process can still be used.
+    poisoned: AtomicBool,
+    /// Embedded in the markers of each command so that they can't be mistaken for output.
+    signal_num: AtomicU64,
+    /// Detected on first use, and again after the process is respawned.
+    capabilities: Mutex<Option<Capabilities>>,
+    /// The arguments of the most recent call, for [`ExifTool::last_command`].
+    last_command: Mutex<Vec<String>>,
+}
+
+impl ExifTool {
+    /// Spawns exiftool, as found in the `EXIFTOOL` environment variable or on `PATH`.
+    ///
+    /// # Panics
+    /// Panics if exiftool could not be spawned. Use [`ExifTool::try_new`] to handle this case.
+    pub fn new() -> Self {
+        Self::try_new().unwrap()
+    }
+
+    pub fn try_new() -> Result<Self, ExifToolError> {
+        Self::from_builder(ExifToolBuilder::new())
+    }
+
+    /// Spawns exiftool as configured by `builder`.
+    pub fn from_builder(builder: ExifToolBuilder) -> Result<Self, ExifToolError> {
+        let config = builder.resolve()?;
+        Ok(ExifTool {
+            process: Mutex::new(Process::spawn(&config)?),
+            config,
+            poisoned: AtomicBool::new(false),
+            signal_num: AtomicU64::new(initial_signal_num()),
+            capabilities: Mutex::new(None),
+            last_command: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Locks the process for a call, respawning it first if it has exited and
+    /// `auto_restart` is set.
+    fn lock_process(&self) -> Result<MutexGuard<'_, Process>, ExifToolError> {
+        let mut process = self.process.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(status) = process.child.try_wait()? {
+            if !self.config.auto_restart {
+                return Err(ExifToolError::Exited(status.into()));
+            }
+            *process = Process::spawn(&self.config)?;
+            self.poisoned.store(false, Ordering::Release);
+            *self.capabilities.lock().unwrap() = None;
+        }
+        if self.poisoned.load(Ordering::Acquire) {
+            return Err(ExifToolError::Poisoned);
+        }
+        Ok(process)
+    }
+
+    /// Sends `message` to the locked `process` and collects its response, leaving the
+    /// process poisoned if this panics or fails partway through.
+    fn communicate(
+        &self,
+        process: &mut Process,
+        message: &[u8],
+        markers: &Markers,
+    ) -> Result<(Vec<u8>, Vec<u8>), ExifToolError> {
+        self.poisoned.store(true, Ordering::Release);
+        let response = process.communicate(message, markers)?;
+        self.poisoned.store(false, Ordering::Release);
+        Ok(response)
+    }
+
+    /// Kills the exiftool process and spawns a new one, clearing any poisoning.
+    pub fn reset(&self) -> Result<(), ExifToolError> {
+        let mut process = self.process.lock().unwrap_or_else(PoisonError::into_inner);
+        let _ = process.child.kill();
+        *process = Process::spawn(&self.config)?;
+        self.poisoned.store(false, Ordering::Release);
+        *self.capabilities.lock().unwrap() = None;
+        Ok(())
+    }
+
+    fn command(
+        &self,
+        params: Vec<String>,
+        with_status: bool,
+    ) -> Result<(Vec<u8>, Markers), ExifToolError> {
+        *self.last_command.lock().unwrap() = self.config.command_line(params.clone());
+        let signal_num = self.signal_num.fetch_add(1, Ordering::Relaxed);
+        self.config.command(params, signal_num, with_status)
+    }
+
+    /// The arguments of the most recent call, including those set on the builder. Running
+    /// exiftool with them reproduces the call. As with the async backend, every command is
+    /// also logged at debug level.
+    pub fn last_command(&self) -> Vec<String> {
+        self.last_command.lock().unwrap().clone()
+    }
+
+    /// What the running exiftool supports, which is detected the first time it is needed,
+    /// and cached until the process is respawned.
+    fn capabilities(&self) -> Result<Capabilities, ExifToolError> {
+        if let Some(capabilities) = *self.capabilities.lock().unwrap() {
+            return Ok(capabilities);
+        }
+        let (message, markers) = self.command(vec!["-ver".to_string()], false)?;
+        let mut process = self.lock_process()?;
+        let (raw_stdout, _) = self.communicate(&mut process, &message, &markers)?;
+        let capabilities = Capabilities::detect(&parse_stdout(raw_stdout, &markers)?);
+        *self.capabilities.lock().unwrap() = Some(capabilities);
+        Ok(capabilities)
+    }
+
+    pub fn execute(&self, params: Vec<String>) -> Result<ExifToolOutput, ExifToolError> {
+        let with_status = self.capabilities()?.status;
+        let (message, markers) = self.command(params, with_status)?;
+        let mut process = self.lock_process()?;
+        let (raw_stdout, raw_stderr) = self.communicate(&mut process, &message, &markers)?;
+
+        parse_response(raw_stdout, raw_stderr, &markers)
+    }
+
+    /// Queries the version of the running exiftool as `(major, minor)`, such as `(12, 40)`.
+    /// This is cached, so only the first call asks exiftool.
+    pub fn version(&self) -> Result<(u32, u32), ExifToolError> {
+        if let Some(version) = self.capabilities()?.version {
+            return Ok(version);
+        }
+        let output = self.execute(vec!["-ver".to_string()])?.output;
+        parse_version(&output).ok_or(ExifToolError::InvalidValue {
+            tag: "version".to_string(),
+            value: String::from_utf8_lossy(&output).into_owned(),
+        })
+    }
+
+    pub fn execute_json(&self, params: Vec<String>) -> Result<Value, ExifToolError> {
+        self.execute_struct(params)
+    }
+
+    /// Runs exiftool with `-j` and deserializes its output, which is always an array with
+    /// an element per file, into `T`.
+    pub fn execute_struct<T: DeserializeOwned>(
+        &self,
+        mut params: Vec<String>,
+    ) -> Result<T, ExifToolError> {
+        params.insert(0, "-j".to_string());
+        self.execute(params)?.parse_json()
+    }
+
+    pub fn get_tags(
+        &self,
+        mut params: Vec<String>,
+        tags: Vec<String>,
+        files: Vec<String>,
+    ) -> Result<Value, ExifToolError> {
+        params.extend(tags.into_iter().map(|tag| format!("-{tag}")));
+        params.extend(files);
+        self.execute_json(params)
+    }
+
+    pub fn preview(&self, path: &str) -> Result<Vec<u8>, ExifToolError> {
+        let output = self.execute(vec![
+            "-b".to_string(),
+            "-PreviewImage".to_string(),
+            path.to_string(),
+        ])?;
+        if output.output.is_empty() {
+            return Err(output.missing_tag_error("PreviewImage"));
+        }
+        Ok(output.output)
+    }
+}
+
+impl Default for ExifTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
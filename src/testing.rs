@@ -0,0 +1,96 @@
+//! Stand-ins for [`ExifTool`](crate::ExifTool), for testing code which uses exiftool without
+//! running it.
+
+use std::collections::HashMap;
+use std::future::{ready, Future};
+
+use serde_json::Value;
+
+use crate::{ExifToolApi, ExifToolError, ExifToolOutput};
+
+/// An [`ExifToolApi`] which answers each call with a canned response, keyed by the exact
+/// arguments it expects. Calls with any other arguments fail with
+/// [`ExifToolError::InvalidArgument`].
+///
+/// ```
+/// use exiftool::testing::MockExifTool;
+/// use exiftool::ExifToolApi;
+/// use serde_json::json;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), exiftool::ExifToolError> {
+/// let mock = MockExifTool::new().respond(
+///     ["-Make", "photo.jpg"],
+///     json!([{"SourceFile": "photo.jpg", "Make": "Canon"}]),
+/// );
+/// let tags = mock.get_tags(vec![], vec!["Make".into()], vec!["photo.jpg".into()]).await?;
+/// assert_eq!(tags[0]["Make"], "Canon");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockExifTool {
+    responses: HashMap<Vec<String>, Value>,
+    previews: HashMap<String, Vec<u8>>,
+}
+
+impl MockExifTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers calls with exactly `params` with `value`. [`ExifToolApi::execute_json`] and
+    /// [`ExifToolApi::get_tags`] return it as is, while [`ExifToolApi::execute`] returns it
+    /// as JSON on stdout, with status 0. Arguments are matched as passed to the mock, so
+    /// `-j` is only part of them if the caller passes it.
+    pub fn respond<S: Into<String>>(
+        mut self,
+        params: impl IntoIterator<Item = S>,
+        value: Value,
+    ) -> Self {
+        let params = params.into_iter().map(Into::into).collect();
+        self.responses.insert(params, value);
+        self
+    }
+
+    /// Answers [`ExifToolApi::preview`] for `path` with `bytes`. Other paths fail with
+    /// [`ExifToolError::MissingTag`], as a file without a preview would.
+    pub fn preview_image(mut self, path: impl Into<String>, bytes: Vec<u8>) -> Self {
+        self.previews.insert(path.into(), bytes);
+        self
+    }
+
+    fn response(&self, params: &[String]) -> Result<Value, ExifToolError> {
+        self.responses.get(params).cloned().ok_or_else(|| {
+            ExifToolError::InvalidArgument(format!("MockExifTool has no response for {params:?}"))
+        })
+    }
+}
+
+impl ExifToolApi for MockExifTool {
+    fn execute(
+        &self,
+        params: Vec<String>,
+    ) -> impl Future<Output = Result<ExifToolOutput, ExifToolError>> + Send {
+        ready(self.response(&params).and_then(|value| {
+            let output = serde_json::to_vec(&value)?;
+            Ok(ExifToolOutput::new(0, output, Vec::new()))
+        }))
+    }
+
+    fn execute_json(
+        &self,
+        params: Vec<String>,
+    ) -> impl Future<Output = Result<Value, ExifToolError>> + Send {
+        ready(self.response(&params))
+    }
+
+    fn preview(&self, path: &str) -> impl Future<Output = Result<Vec<u8>, ExifToolError>> + Send {
+        ready(
+            self.previews
+                .get(path)
+                .cloned()
+                .ok_or_else(|| ExifToolError::MissingTag("PreviewImage".to_string())),
+        )
+    }
+}
@@ -0,0 +1,59 @@
+use serde_json::Value;
+
+use crate::{find_tag, value_as_f64, ExifTool, ExifToolError};
+
+/// What is known about an embedded thumbnail without extracting it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThumbnailInfo {
+    /// The width in pixels, if the file records it. EXIF thumbnails usually don't.
+    pub width: Option<u32>,
+    /// The height in pixels, if the file records it.
+    pub height: Option<u32>,
+    /// The size of the thumbnail in bytes.
+    pub length: u64,
+}
+
+impl ExifTool {
+    /// Reads the size of the `ThumbnailImage` of `file`, and its dimensions where recorded,
+    /// without extracting it. Returns `None` if the file has no thumbnail.
+    pub async fn thumbnail_info(&self, file: &str) -> Result<Option<ThumbnailInfo>, ExifToolError> {
+        let tags = self
+            .get_tags_for_file(
+                file,
+                &[
+                    "ThumbnailImage",
+                    "ThumbnailLength#",
+                    "IFD1:ImageWidth#",
+                    "IFD1:ImageHeight#",
+                ],
+            )
+            .await?;
+        // Without -b, the thumbnail itself is printed as a placeholder giving its size,
+        // which covers formats that have no ThumbnailLength.
+        let placeholder_length = find_tag(&tags, "ThumbnailImage")
+            .and_then(Value::as_str)
+            .and_then(|value| value.strip_prefix("(Binary data "))
+            .and_then(|value| value.split(' ').next())
+            .and_then(|length| length.parse().ok());
+        let length = match placeholder_length.or_else(|| {
+            find_tag(&tags, "ThumbnailLength")
+                .and_then(value_as_f64)
+                .filter(|length| *length > 0.0)
+                .map(|length| length as u64)
+        }) {
+            Some(length) => length,
+            None => return Ok(None),
+        };
+        let pixels = |tag: &str| {
+            find_tag(&tags, tag)
+                .and_then(value_as_f64)
+                .filter(|n| *n >= 0.0 && *n <= u32::MAX as f64)
+                .map(|n| n.round() as u32)
+        };
+        Ok(Some(ThumbnailInfo {
+            width: pixels("ImageWidth"),
+            height: pixels("ImageHeight"),
+            length,
+        }))
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+use crate::{BackupMode, ExifTool, ExifToolError, WriteSummary};
+
+/// An amount to move dates and times by. It displays as the magnitude in exiftool's
+/// `Y:M:D h:m:s` shift format, with the direction given separately by `backwards`.
+///
+/// ```
+/// let shift = exiftool::TimeShift::hours(-3).and_minutes(30);
+/// assert!(shift.backwards);
+/// assert_eq!(shift.to_string(), "0:0:0 3:30:0");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeShift {
+    /// Whether to shift backwards. The other fields are magnitudes.
+    pub backwards: bool,
+    pub years: u32,
+    pub months: u32,
+    pub days: u32,
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+}
+
+impl TimeShift {
+    /// A shift by a whole number of hours, the usual fix for a camera clock set to the
+    /// wrong timezone. Negative values shift backwards.
+    pub fn hours(hours: i32) -> Self {
+        TimeShift {
+            backwards: hours < 0,
+            hours: hours.unsigned_abs(),
+            ..TimeShift::default()
+        }
+    }
+
+    /// A shift by a number of days. Negative values shift backwards.
+    pub fn days(days: i32) -> Self {
+        TimeShift {
+            backwards: days < 0,
+            days: days.unsigned_abs(),
+            ..TimeShift::default()
+        }
+    }
+
+    /// Adds `minutes` to the shift, in the same direction.
+    pub fn and_minutes(mut self, minutes: u32) -> Self {
+        self.minutes = minutes;
+        self
+    }
+
+    /// Adds `seconds` to the shift, in the same direction.
+    pub fn and_seconds(mut self, seconds: u32) -> Self {
+        self.seconds = seconds;
+        self
+    }
+}
+
+impl fmt::Display for TimeShift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{} {}:{}:{}",
+            self.years, self.months, self.days, self.hours, self.minutes, self.seconds
+        )
+    }
+}
+
+impl ExifTool {
+    /// Shifts the capture, digitization and modification times of `file` (exiftool's
+    /// `AllDates`) by `shift`. Fails with [`ExifToolError::MissingTag`] if the file has none
+    /// of those tags to shift.
+    pub async fn shift_datetime(
+        &self,
+        file: &str,
+        shift: TimeShift,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        let op = if shift.backwards { "-=" } else { "+=" };
+        self.write_all_dates(file, vec![format!("-AllDates{op}{shift}")], backup)
+            .await
+    }
+
+    /// Sets the capture, digitization and modification times of `file` (exiftool's
+    /// `AllDates`) to `datetime`, in exiftool's `YYYY:MM:DD HH:MM:SS` format.
+    pub async fn set_datetime(
+        &self,
+        file: &str,
+        datetime: &str,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        self.write_all_dates(file, vec![format!("-AllDates={datetime}")], backup)
+            .await
+    }
+
+    pub(crate) async fn write_all_dates(
+        &self,
+        file: &str,
+        mut params: Vec<String>,
+        backup: BackupMode,
+    ) -> Result<WriteSummary, ExifToolError> {
+        params.extend(backup.arg().map(str::to_string));
+        params.push(file.to_string());
+        let summary = self.execute(params).await?.check_updated()?;
+        // exiftool leaves the file unchanged, rather than failing, both if it has no dates
+        // to shift and if they already have the values asked for.
+        if summary.unchanged == 1 {
+            let dates = self
+                .get_tags_for_file(file, &["DateTimeOriginal", "CreateDate", "ModifyDate"])
+                .await?;
+            if !dates.keys().any(|tag| tag != "SourceFile") {
+                return Err(ExifToolError::MissingTag("AllDates".to_string()));
+            }
+        }
+        Ok(summary)
+    }
+}
@@ -0,0 +1,75 @@
+use serde_json::Value;
+
+use crate::{ExifTool, ExifToolError, OrderedObject};
+
+/// The structural problems exiftool's `-validate` found in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Whether no problems were found.
+    pub is_valid: bool,
+    /// exiftool's summary, such as `OK` or `2 Warnings (all minor)`.
+    pub summary: String,
+    pub issues: Vec<ValidationIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    /// The family 1 group of the tag exiftool reported the issue as, such as `ExifTool`.
+    pub group: String,
+    pub message: String,
+    /// Whether this is an error rather than a warning.
+    pub is_error: bool,
+}
+
+impl ExifTool {
+    /// Checks the metadata structure of `file` with exiftool's `-validate`, returning every
+    /// warning and error it found.
+    pub async fn validate(&self, file: &str) -> Result<ValidationReport, ExifToolError> {
+        // -a keeps every warning, which only the ordered parse can see, as they are all
+        // printed under the same key.
+        let OrderedObject(tags) = self
+            .execute_struct_one(vec![
+                "-validate".to_string(),
+                "-warning".to_string(),
+                "-error".to_string(),
+                "-a".to_string(),
+                "-G1".to_string(),
+                file.to_string(),
+            ])
+            .await?;
+
+        let mut summary = None;
+        let mut issues = Vec::new();
+        for (key, value) in tags {
+            let (group, tag) = key.rsplit_once(':').unwrap_or(("", &key));
+            let message = match value {
+                Value::String(s) => s,
+                value => value.to_string(),
+            };
+            match tag {
+                "Validate" => summary = Some(message),
+                "Warning" | "Error" => issues.push(ValidationIssue {
+                    group: group.to_string(),
+                    message,
+                    is_error: tag == "Error",
+                }),
+                _ => {}
+            }
+        }
+        // Without a summary, the file couldn't be validated at all, such as if it's missing.
+        let summary = match summary {
+            Some(summary) => summary,
+            None => {
+                return Err(match issues.into_iter().find(|issue| issue.is_error) {
+                    Some(issue) => ExifToolError::Reported(issue.message),
+                    None => ExifToolError::MissingTag("Validate".to_string()),
+                })
+            }
+        };
+        Ok(ValidationReport {
+            is_valid: issues.is_empty(),
+            summary,
+            issues,
+        })
+    }
+}
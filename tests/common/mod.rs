@@ -0,0 +1,58 @@
+//! Helpers for the integration tests, which run a real exiftool. Each test returns early,
+//! passing, when exiftool isn't installed.
+
+// Each test binary uses only some of these.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use exiftool::{ExifTool, ExifToolBuilder};
+
+/// Spawns exiftool as configured by `builder`, or returns `None` if it can't be started.
+pub fn exiftool_with(builder: ExifToolBuilder) -> Option<ExifTool> {
+    match builder.build() {
+        Ok(exiftool) => Some(exiftool),
+        Err(e) => {
+            eprintln!("skipping, as exiftool can't be started: {e}");
+            None
+        }
+    }
+}
+
+pub fn exiftool() -> Option<ExifTool> {
+    exiftool_with(ExifTool::builder())
+}
+
+/// A directory of its own for one test, which is removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory, named after `test`, under the system's temp directory.
+    pub fn new(test: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("exiftool-rs-{}-{test}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// Copies `tests/fixtures/{fixture}` into the directory as `name`, returning its path.
+    pub fn fixture(&self, fixture: &str, name: &str) -> PathBuf {
+        let source = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(fixture);
+        let path = self.0.join(name);
+        fs::copy(source, &path).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::time::Duration;
+
+use exiftool::{CancellationToken, ExifToolError};
+
+#[tokio::test]
+async fn timeout_poisons_until_drained() {
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+    // Detects what exiftool supports first, so that only the slow call below is timed.
+    exiftool.version().await.unwrap();
+
+    // Listing every tag exiftool knows as XML takes it a good fraction of a second.
+    let result = exiftool
+        .execute_with_timeout(vec!["-listx".to_string()], Duration::from_millis(1))
+        .await;
+    assert!(matches!(result, Err(ExifToolError::Timeout)));
+    let result = exiftool.execute(vec!["-ver".to_string()]).await;
+    assert!(matches!(result, Err(ExifToolError::Poisoned)));
+
+    exiftool.drain().await.unwrap();
+    let output = exiftool.execute(vec!["-ver".to_string()]).await.unwrap();
+    assert!(output.stdout_str().trim().starts_with(char::is_numeric));
+}
+
+#[tokio::test]
+async fn cancel_then_reuse() {
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+    exiftool.version().await.unwrap();
+
+    // Cancelled once exiftool has begun printing, so that part of the response was read.
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        cancel.cancel();
+    });
+    let result = exiftool
+        .execute_with_cancel(vec!["-listx".to_string()], token)
+        .await;
+    assert!(matches!(result, Err(ExifToolError::Cancelled)));
+
+    let output = exiftool.execute(vec!["-ver".to_string()]).await.unwrap();
+    assert!(output.stdout_str().trim().starts_with(char::is_numeric));
+}
@@ -0,0 +1,222 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use common::TempDir;
+use exiftool::{decode_base64_tag, BackupMode, ExifTool, ExifToolError, FileType, GroupMode};
+use serde::Deserialize;
+
+#[tokio::test]
+async fn read_non_ascii_file_name() {
+    let dir = TempDir::new("read_non_ascii_file_name");
+    let photo = dir.fixture("blank.jpg", "café naïve.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool_with(ExifTool::builder().charset("utf8")) else {
+        return;
+    };
+
+    let tags = exiftool
+        .get_tags_for_file(photo, &["ImageWidth"])
+        .await
+        .unwrap();
+    assert_eq!(tags["SourceFile"], photo);
+    assert_eq!(tags["ImageWidth"], 16);
+}
+
+#[tokio::test]
+async fn is_supported_by_contents() {
+    let dir = TempDir::new("is_supported_by_contents");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    // exiftool reads plain text files too, so this is bytes which are nothing it knows.
+    let unknown = dir.path().join("data.unknown");
+    std::fs::write(&unknown, [0x00, 0xa5, 0x5a, 0xff, 0x13, 0x37, 0x00, 0x01]).unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let supported = exiftool.is_supported(photo.to_str().unwrap()).await;
+    assert!(supported.unwrap());
+    let supported = exiftool.is_supported(unknown.to_str().unwrap()).await;
+    assert!(!supported.unwrap());
+}
+
+#[tokio::test]
+async fn structured_regions() {
+    let dir = TempDir::new("structured_regions");
+    let sidecar = dir.fixture("regions.xmp", "photo.xmp");
+    let Some(exiftool) = common::exiftool_with(ExifTool::builder().structured(true)) else {
+        return;
+    };
+
+    let tags = exiftool
+        .get_tags_for_file(sidecar.to_str().unwrap(), &["RegionInfo"])
+        .await
+        .unwrap();
+    let regions = &tags["RegionInfo"];
+    assert_eq!(regions["AppliedToDimensions"]["W"], 16);
+    let list = regions["RegionList"].as_array().unwrap();
+    let names: Vec<_> = list.iter().map(|region| &region["Name"]).collect();
+    assert_eq!(names, ["Jane", "John"]);
+    assert_eq!(list[0]["Area"]["X"], 0.25);
+}
+
+// Without the file name charset, this fails on Windows unless the system code page has the
+// characters. Elsewhere exiftool takes file names as bytes either way.
+#[tokio::test]
+async fn read_cjk_file_name() {
+    let dir = TempDir::new("read_cjk_file_name");
+    let photo = dir.fixture("blank.jpg", "写真.jpg");
+    let photo = photo.to_str().unwrap();
+    let builder = ExifTool::builder().filename_charset("utf8");
+    let Some(exiftool) = common::exiftool_with(builder) else {
+        return;
+    };
+
+    let tags = exiftool
+        .get_tags_for_file(photo, &["FileName"])
+        .await
+        .unwrap();
+    assert_eq!(tags["FileName"], "写真.jpg");
+}
+
+#[tokio::test]
+async fn display_dimensions_of_rotated_photo() {
+    let dir = TempDir::new("display_dimensions_of_rotated_photo");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let dimensions = exiftool.display_dimensions(photo).await.unwrap();
+    assert_eq!(dimensions, Some((16, 8)));
+    // Orientation 6 has viewers turn the photo a quarter turn clockwise.
+    let orientation = [("Orientation#".to_string(), "6".to_string())];
+    exiftool
+        .write_tags(photo, &orientation, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    let dimensions = exiftool.display_dimensions(photo).await.unwrap();
+    assert_eq!(dimensions, Some((8, 16)));
+}
+
+#[tokio::test]
+async fn read_into_struct() {
+    #[derive(Deserialize)]
+    struct Summary {
+        #[serde(rename = "FileName")]
+        file_name: String,
+        #[serde(rename = "MIMEType")]
+        mime_type: String,
+    }
+
+    let dir = TempDir::new("read_into_struct");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let summary: Summary = exiftool
+        .execute_struct_one(vec![
+            "-FileName".to_string(),
+            "-MIMEType".to_string(),
+            photo.to_str().unwrap().to_string(),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(summary.file_name, "photo.jpg");
+    assert_eq!(summary.mime_type, "image/jpeg");
+}
+
+#[tokio::test]
+async fn preview_of_photo_without_one() {
+    let dir = TempDir::new("preview_of_photo_without_one");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let result = exiftool.preview(photo.to_str().unwrap()).await;
+    match result {
+        Err(ExifToolError::MissingTag(tag)) => assert_eq!(tag, "PreviewImage"),
+        Err(e) => panic!("{e:?}"),
+        Ok(preview) => panic!("read a preview of {} bytes", preview.len()),
+    }
+}
+
+#[tokio::test]
+async fn read_embedded_thumbnail() {
+    let dir = TempDir::new("read_embedded_thumbnail");
+    // blank.jpg, with itself embedded as the EXIF thumbnail.
+    let photo = dir.fixture("thumbnail.jpg", "photo.jpg");
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let tags = exiftool
+        .execute_json_binary(vec![
+            "-ThumbnailImage".to_string(),
+            "-ImageWidth".to_string(),
+            photo.to_str().unwrap().to_string(),
+        ])
+        .await
+        .unwrap();
+    let thumbnail = decode_base64_tag(&tags[0]["ThumbnailImage"]).unwrap();
+    let blank = std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/blank.jpg"
+    ));
+    assert_eq!(thumbnail, blank.unwrap());
+    assert_eq!(tags[0]["ImageWidth"], 16);
+}
+
+#[tokio::test]
+async fn file_type_of_jpeg() {
+    let dir = TempDir::new("file_type_of_jpeg");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    assert_eq!(exiftool.file_type(photo).await.unwrap(), FileType::Jpeg);
+    assert_eq!(exiftool.mime_type(photo).await.unwrap(), "image/jpeg");
+}
+
+#[tokio::test]
+async fn tag_in_exif_and_xmp() {
+    let dir = TempDir::new("tag_in_exif_and_xmp");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+    let artists = [
+        ("EXIF:Artist".to_string(), "in EXIF".to_string()),
+        ("XMP:Artist".to_string(), "in XMP".to_string()),
+    ];
+    exiftool
+        .write_tags(photo, &artists, BackupMode::Overwrite)
+        .await
+        .unwrap();
+
+    // Without groups, exiftool picks the EXIF one.
+    let tags = exiftool
+        .get_tags_for_file(photo, &["Artist"])
+        .await
+        .unwrap();
+    assert_eq!(tags["Artist"], "in EXIF");
+
+    let builder = ExifTool::builder()
+        .group_mode(GroupMode::Group0)
+        .allow_duplicates(true);
+    let Some(exiftool) = common::exiftool_with(builder) else {
+        return;
+    };
+    let tags = exiftool
+        .get_tags_for_file(photo, &["Artist"])
+        .await
+        .unwrap();
+    assert_eq!(tags["EXIF:Artist"], "in EXIF");
+    assert_eq!(tags["XMP:Artist"], "in XMP");
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "sync")]
+
+use exiftool::sync::ExifTool;
+
+#[test]
+fn reset_respawns() {
+    // The async helpers in `common` aren't built without tokio.
+    let exiftool = match ExifTool::try_new() {
+        Ok(exiftool) => exiftool,
+        Err(e) => {
+            eprintln!("skipping, as exiftool can't be started: {e}");
+            return;
+        }
+    };
+
+    let version = exiftool.version().unwrap();
+    exiftool.reset().unwrap();
+    assert_eq!(exiftool.version().unwrap(), version);
+    let output = exiftool.execute(vec!["-ver".to_string()]).unwrap();
+    assert!(output.stdout_str().trim().starts_with(char::is_numeric));
+}
@@ -0,0 +1,316 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use std::path::Path;
+
+use common::TempDir;
+use exiftool::{BackupMode, ExifTool, ExifToolError, TimeShift};
+use serde::{Deserialize, Serialize};
+
+#[tokio::test]
+async fn write_sidecar_in_current_dir() {
+    let dir = TempDir::new("write_sidecar_in_current_dir");
+    dir.fixture("blank.jpg", "photo.jpg");
+    let builder = ExifTool::builder().current_dir(dir.path());
+    let Some(exiftool) = common::exiftool_with(builder) else {
+        return;
+    };
+
+    let title = |title: &str| [("XMP:Title".to_string(), title.to_string())];
+    exiftool
+        .write_sidecar("photo.jpg", "photo.xmp", &title("first"))
+        .await
+        .unwrap();
+    // The sidecar now exists relative to exiftool's directory, so is updated in place.
+    exiftool
+        .write_sidecar("photo.jpg", "photo.xmp", &title("second"))
+        .await
+        .unwrap();
+
+    let value = exiftool.get_value("photo.xmp", "XMP:Title").await.unwrap();
+    assert_eq!(value.as_deref(), Some("second"));
+}
+
+#[tokio::test]
+async fn write_and_delete_comment() {
+    let dir = TempDir::new("write_and_delete_comment");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let comment = |value: &str| [("Comment".to_string(), value.to_string())];
+    let summary = exiftool
+        .write_tags(photo, &comment("written by a test"), BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(summary.updated, 1);
+    let value = exiftool.get_value(photo, "Comment").await.unwrap();
+    assert_eq!(value.as_deref(), Some("written by a test"));
+
+    exiftool
+        .write_tags(photo, &comment(""), BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(exiftool.get_value(photo, "Comment").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn write_summary_counts_files() {
+    let dir = TempDir::new("write_summary_counts_files");
+    let first = dir.fixture("blank.jpg", "first.jpg");
+    let second = dir.fixture("blank.jpg", "second.jpg");
+    let missing = dir.path().join("missing.jpg");
+    let (first, second, missing) = (
+        first.to_str().unwrap(),
+        second.to_str().unwrap(),
+        missing.to_str().unwrap(),
+    );
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let artist = [("Artist".to_string(), "Someone".to_string())];
+    let summary = exiftool
+        .write_tags(first, &artist, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(
+        (summary.updated, summary.unchanged, summary.errors),
+        (1, 0, 0)
+    );
+
+    let summary = exiftool
+        .write_tags_batch(&[first, second, missing], &artist, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(
+        (summary.updated, summary.unchanged, summary.errors),
+        (1, 1, 1)
+    );
+    assert_eq!(summary.failures.len(), 1);
+    assert_eq!(summary.failures[0].0, Path::new(missing));
+}
+
+#[tokio::test]
+async fn minor_errors_fail_unless_ignored() {
+    let dir = TempDir::new("minor_errors_fail_unless_ignored");
+    // Canon maker notes with an entry of no valid format, which exiftool can't rewrite.
+    let photo = dir.fixture("bad_maker_notes.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let artist = [("Artist".to_string(), "Someone".to_string())];
+
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+    let result = exiftool
+        .write_tags(photo, &artist, BackupMode::Overwrite)
+        .await;
+    match result {
+        Err(ExifToolError::MinorError(message)) => assert!(message.contains("[minor]")),
+        Err(e) => panic!("{e:?}"),
+        Ok(_) => panic!("wrote past a minor error"),
+    }
+
+    let builder = ExifTool::builder().ignore_minor_errors(true);
+    let Some(exiftool) = common::exiftool_with(builder) else {
+        return;
+    };
+    let summary = exiftool
+        .write_tags(photo, &artist, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(summary.updated, 1);
+    assert!(summary
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("[minor]")));
+}
+
+#[tokio::test]
+async fn backup_modes() {
+    let dir = TempDir::new("backup_modes");
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let artist = [("Artist".to_string(), "Someone".to_string())];
+    for (mode, name, backed_up) in [
+        (BackupMode::Backup, "backup.jpg", true),
+        (BackupMode::Overwrite, "overwrite.jpg", false),
+        (BackupMode::OverwriteInPlace, "in_place.jpg", false),
+    ] {
+        let photo = dir.fixture("blank.jpg", name);
+        exiftool
+            .write_tags(photo.to_str().unwrap(), &artist, mode)
+            .await
+            .unwrap();
+        let backup = dir.path().join(format!("{name}_original"));
+        assert_eq!(backup.exists(), backed_up, "{mode:?}");
+    }
+}
+
+#[tokio::test]
+async fn write_same_value_twice() {
+    let dir = TempDir::new("write_same_value_twice");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let artist = [("Artist".to_string(), "Someone".to_string())];
+    let summary = exiftool
+        .write_tags(photo, &artist, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!((summary.updated, summary.unchanged), (1, 0));
+    // exiftool finds the file already as asked, and leaves it alone.
+    let summary = exiftool
+        .write_tags(photo, &artist, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!((summary.updated, summary.unchanged), (0, 1));
+}
+
+#[tokio::test]
+async fn dates_left_unchanged() {
+    let dir = TempDir::new("dates_left_unchanged");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let result = exiftool
+        .shift_datetime(photo, TimeShift::hours(1), BackupMode::Overwrite)
+        .await;
+    assert!(matches!(result, Err(ExifToolError::MissingTag(_))));
+
+    let date = "2020:01:02 03:04:05";
+    let summary = exiftool
+        .set_datetime(photo, date, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(summary.updated, 1);
+    // The dates exist, so these are no-ops rather than missing dates.
+    let summary = exiftool
+        .set_datetime(photo, date, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(summary.unchanged, 1);
+    let summary = exiftool
+        .shift_datetime(photo, TimeShift::default(), BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(summary.unchanged, 1);
+}
+
+#[tokio::test]
+async fn list_tag_items_with_commas() {
+    let dir = TempDir::new("list_tag_items_with_commas");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let keyword = |value: &str| ("Keywords".to_string(), value.to_string());
+    exiftool
+        .write_tags(photo, &[keyword("Smith, John")], BackupMode::Overwrite)
+        .await
+        .unwrap();
+    let keywords = exiftool.get_list_tag(photo, "Keywords").await.unwrap();
+    assert_eq!(keywords, ["Smith, John"]);
+
+    exiftool
+        .write_tags(
+            photo,
+            &[keyword("a"), keyword("b, c")],
+            BackupMode::Overwrite,
+        )
+        .await
+        .unwrap();
+    let keywords = exiftool.get_list_tag(photo, "Keywords").await.unwrap();
+    assert_eq!(keywords, ["a", "b, c"]);
+}
+
+#[tokio::test]
+async fn remove_tags_and_nothing_to_remove() {
+    let dir = TempDir::new("remove_tags_and_nothing_to_remove");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let artist = [("Artist".to_string(), "Someone".to_string())];
+    exiftool
+        .write_tags(photo, &artist, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    let summary = exiftool
+        .remove_tags(photo, &["Artist"], BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!(summary.updated, 1);
+    assert_eq!(exiftool.get_value(photo, "Artist").await.unwrap(), None);
+
+    // Removing what isn't there leaves the file unchanged, which isn't an error.
+    let summary = exiftool
+        .remove_tags(photo, &["Artist", "gps:all"], BackupMode::Overwrite)
+        .await
+        .unwrap();
+    assert_eq!((summary.updated, summary.unchanged), (0, 1));
+}
+
+#[tokio::test]
+async fn write_struct_round_trip() {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct PhotoEdits {
+        artist: Option<String>,
+        copyright: Option<String>,
+        keywords: Vec<String>,
+    }
+
+    let dir = TempDir::new("write_struct_round_trip");
+    let photo = dir.fixture("blank.jpg", "photo.jpg");
+    let photo = photo.to_str().unwrap();
+    let Some(exiftool) = common::exiftool() else {
+        return;
+    };
+
+    let copyright = [("Copyright".to_string(), "kept".to_string())];
+    exiftool
+        .write_tags(photo, &copyright, BackupMode::Overwrite)
+        .await
+        .unwrap();
+    let edits = PhotoEdits {
+        artist: Some("Someone".to_string()),
+        // Left as it is, rather than cleared.
+        copyright: None,
+        keywords: vec!["a".to_string(), "b, c".to_string()],
+    };
+    exiftool
+        .write_struct(photo, &edits, BackupMode::Overwrite)
+        .await
+        .unwrap();
+
+    let read: PhotoEdits = exiftool
+        .execute_struct_one(vec![
+            "-Artist".to_string(),
+            "-Copyright".to_string(),
+            "-Keywords".to_string(),
+            photo.to_string(),
+        ])
+        .await
+        .unwrap();
+    let expected = PhotoEdits {
+        copyright: Some("kept".to_string()),
+        ..edits
+    };
+    assert_eq!(read, expected);
+}